@@ -0,0 +1,456 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail};
+use calculate::models::card::{
+    parse_cards, Board, Card, CardNotation, CardSet, HandCategory, NotatedCard,
+};
+use calculate::models::model::{
+    CalculateOutsReq, CalculateRatingReq, HandHistory, PushFoldReq, UserCards, VerifyShuffleReq,
+};
+use calculate::services::hand_history::parse_pokerstars;
+use calculate::services::shuffle::verify_shuffle;
+use calculate::Evaluator;
+use serde_json::json;
+
+const USAGE: &str = "usage:
+  calculate                                    启动HTTP服务
+  calculate equity <hand>... [--board CARDS] [--dead CARDS] [--exact] [--seed N] [--json]
+  calculate outs <hand>... --board CARDS [--dead CARDS] [--json]
+  calculate eval <cards> [--json]
+  calculate history <file|-> [--json]          标注牌局记录(json或PokerStars文本)中全下和摊牌的胜率
+  calculate allin-ev <file|-> [--json]         按ndjson格式的全下记录统计全下EV和实际输赢
+  calculate verify [hand|-]... --server-seed S --commitment C --client-seed S --players N
+                   [--nonce N] [--board CARDS] [--json]
+                                               校验手牌(按座位顺序，-表示未知)和公共牌由公开的种子推出
+  calculate push-fold --stack BB [--players N] [--ante BB] [--json]
+                                               短筹码全下或弃牌的纳什均衡图表，结果缓存在磁盘上
+
+example:
+  calculate equity AsKs QdQc --board 7h8h2c --dead 3s";
+
+// 需要带值的参数，其余--开头的参数都是开关
+pub(crate) const VALUE_OPTIONS: [&str; 10] = [
+    "board",
+    "dead",
+    "seed",
+    "server-seed",
+    "commitment",
+    "client-seed",
+    "nonce",
+    "players",
+    "stack",
+    "ante",
+];
+
+// 命令行不受接口超时限制
+const CLI_BUDGET: Duration = Duration::from_secs(60);
+
+pub(crate) struct Args {
+    pub(crate) positional: Vec<String>,
+    pub(crate) options: HashMap<String, String>,
+    pub(crate) flags: HashSet<String>,
+}
+
+impl Args {
+    pub(crate) fn parse(args: &[String]) -> anyhow::Result<Args> {
+        let mut parsed = Args {
+            positional: vec![],
+            options: HashMap::new(),
+            flags: HashSet::new(),
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                parsed.positional.push(arg.clone());
+                continue;
+            };
+            if VALUE_OPTIONS.contains(&name) {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("--{} needs a value", name))?;
+                parsed.options.insert(name.to_string(), value.clone());
+            } else {
+                parsed.flags.insert(name.to_string());
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn cards(&self, name: &str) -> anyhow::Result<Vec<Card>> {
+        match self.options.get(name) {
+            Some(value) => Ok(parse_cards(value)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn board(&self) -> anyhow::Result<Board> {
+        Ok(Board::new(self.cards("board")?)?)
+    }
+
+    fn option(&self, name: &str) -> anyhow::Result<&String> {
+        self.options
+            .get(name)
+            .ok_or_else(|| anyhow!("--{} is required", name))
+    }
+
+    fn json(&self) -> bool {
+        self.flags.contains("json")
+    }
+
+    // 每个位置参数是一手两张的手牌，uid按顺序从1开始
+    pub(crate) fn clients(&self) -> anyhow::Result<Vec<UserCards>> {
+        let mut clients = vec![];
+        for (i, hand) in self.positional.iter().enumerate() {
+            let cards = parse_cards(hand)?;
+            if cards.len() != 2 {
+                bail!("hand {} should have 2 cards", hand);
+            }
+            clients.push(UserCards {
+                hands: [cards[0], cards[1]],
+                uid: (i + 1).to_string(),
+            });
+        }
+        Ok(clients)
+    }
+}
+
+// 返回进程的退出码
+pub fn run(args: &[String]) -> i32 {
+    match execute(args) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            2
+        }
+    }
+}
+
+fn execute(args: &[String]) -> anyhow::Result<i32> {
+    let (command, rest) = args
+        .split_first()
+        .ok_or_else(|| anyhow!("missing command"))?;
+    let args = Args::parse(rest)?;
+    match command.as_str() {
+        "equity" => equity(&args),
+        "outs" => outs(&args),
+        "eval" => eval(&args),
+        "history" => history(&args),
+        "allin-ev" => allin_ev(&args),
+        "verify" => verify(&args),
+        "push-fold" => push_fold(&args),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(0)
+        }
+        _ => bail!("unknown command {}", command),
+    }
+}
+
+fn equity(args: &Args) -> anyhow::Result<i32> {
+    let clients = args.clients()?;
+    let req = CalculateRatingReq {
+        clients: clients.clone(),
+        deal_cards: args.board()?,
+        dead_cards: args.cards("dead")?,
+        exact: args.flags.contains("exact"),
+        target_std_err: None,
+        time_budget_ms: None,
+        seed: args.options.get("seed").map(|x| x.parse()).transpose()?,
+        samples: None,
+        second_board: None,
+    };
+    let rsp = Evaluator {}.calculate_rating_progress(req, CLI_BUDGET, &mut |_| true);
+    if args.json() {
+        println!("{}", serde_json::to_string_pretty(&rsp)?);
+        return Ok(if rsp.code == 0 { 0 } else { 1 });
+    }
+    if rsp.code != 0 {
+        eprintln!("{}", rsp.msg);
+        return Ok(1);
+    }
+    println!("{:<8}{:>10}{:>22}", "hand", "equity", "95% ci");
+    for (client, rate) in clients.iter().zip(rsp.clients_rate.iter()) {
+        let ci = format!(
+            "{}-{}",
+            percent(rate.ci_low.unwrap_or(rate.rate)),
+            percent(rate.ci_high.unwrap_or(rate.rate))
+        );
+        println!(
+            "{:<8}{:>10}{:>22}",
+            hand_string(&client.hands),
+            percent(rate.rate),
+            ci
+        );
+    }
+    println!("samples: {}, seed: {}", rsp.samples, rsp.seed);
+    Ok(0)
+}
+
+fn outs(args: &Args) -> anyhow::Result<i32> {
+    let clients = args.clients()?;
+    let req = CalculateOutsReq {
+        clients: clients.clone(),
+        deal_cards: args.board()?,
+        dead_cards: args.cards("dead")?,
+        notation: CardNotation::Short,
+        second_board: None,
+    };
+    let rsp = Evaluator {}.outs(req);
+    if args.json() {
+        println!("{}", serde_json::to_string_pretty(&rsp)?);
+        return Ok(if rsp.code == 0 { 0 } else { 1 });
+    }
+    if rsp.code != 0 {
+        eprintln!("{}", rsp.msg);
+        return Ok(1);
+    }
+    println!("{:<8}{:>6}  cards", "hand", "outs");
+    for client in &clients {
+        let Some(outs) = rsp.outs.iter().find(|x| x.uid == client.uid) else {
+            continue;
+        };
+        let cards: Vec<String> = outs
+            .cards
+            .iter()
+            .map(|x| match x {
+                NotatedCard::Text(text) => text.clone(),
+                NotatedCard::Id(id) => id.to_string(),
+            })
+            .collect();
+        println!(
+            "{:<8}{:>6}  {}",
+            hand_string(&client.hands),
+            cards.len(),
+            cards.join(" ")
+        );
+    }
+    Ok(0)
+}
+
+fn eval(args: &Args) -> anyhow::Result<i32> {
+    let cards = parse_cards(&args.positional.join(""))?;
+    let set: CardSet = cards.iter().copied().collect();
+    if set.len() != cards.len() || !(5..=7).contains(&cards.len()) {
+        bail!("eval needs 5 to 7 distinct cards");
+    }
+    let value = set.hand().evaluate();
+    let category = HandCategory::of(value);
+    if args.json() {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({"category": category, "value": value}))?
+        );
+    } else {
+        println!("{:?} ({})", category, value);
+    }
+    Ok(0)
+}
+
+fn history(args: &Args) -> anyhow::Result<i32> {
+    let path = args
+        .positional
+        .first()
+        .ok_or_else(|| anyhow!("history needs a file"))?;
+    let text = if path == "-" {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(path)?
+    };
+    // json可以是单手牌或牌局数组，其余按PokerStars文本解析
+    let hands: Vec<HandHistory> = match text.trim_start().chars().next() {
+        Some('[') => serde_json::from_str(&text)?,
+        Some('{') => vec![serde_json::from_str(&text)?],
+        _ => parse_pokerstars(&text),
+    };
+    let evaluator = Evaluator {};
+    let mut annotated = vec![];
+    for hand in &hands {
+        match evaluator.annotate_hand(hand, CardNotation::Short) {
+            Ok(x) => annotated.push(x),
+            Err(reason) => eprintln!("skip hand {}: {}", hand.hand_id, reason),
+        }
+    }
+    if args.json() {
+        println!("{}", serde_json::to_string_pretty(&annotated)?);
+        return Ok(0);
+    }
+    for hand in &annotated {
+        println!(
+            "hand {} ({}) board {}",
+            hand.hand_id, hand.situation, hand.board
+        );
+        print!("{:<16}{:<8}", "uid", "hand");
+        for street in &hand.streets {
+            print!("{:>10}", street.street);
+        }
+        println!();
+        for (i, client) in hand.clients.iter().enumerate() {
+            print!("{:<16}{:<8}", client.uid, hand_string(&client.hands));
+            for street in &hand.streets {
+                print!("{:>10}", percent(street.clients_rate[i].rate));
+            }
+            println!();
+        }
+    }
+    println!("annotated {} of {} hands", annotated.len(), hands.len());
+    Ok(0)
+}
+
+fn allin_ev(args: &Args) -> anyhow::Result<i32> {
+    let path = args
+        .positional
+        .first()
+        .ok_or_else(|| anyhow!("allin-ev needs a file"))?;
+    let reader: Box<dyn BufRead> = if path == "-" {
+        Box::new(std::io::stdin().lock())
+    } else {
+        Box::new(BufReader::new(File::open(path)?))
+    };
+    // --json时逐手输出ndjson，最后一行是汇总
+    let json = args.json();
+    let rsp = Evaluator {}.allin_ev_report(reader, &mut |hand| {
+        if json {
+            println!("{}", serde_json::to_string(hand).unwrap());
+        }
+    });
+    if json {
+        println!("{}", serde_json::to_string(&rsp)?);
+        return Ok(if rsp.code == 0 { 0 } else { 1 });
+    }
+    if rsp.code != 0 {
+        eprintln!("{}", rsp.msg);
+        return Ok(1);
+    }
+    for skipped in &rsp.skipped {
+        eprintln!("skip hand {}: {}", skipped.hand_id, skipped.reason);
+    }
+    println!(
+        "{:<16}{:>8}{:>14}{:>14}{:>14}",
+        "uid", "hands", "all-in ev", "actual", "luck"
+    );
+    for player in &rsp.players {
+        println!(
+            "{:<16}{:>8}{:>14.2}{:>14}{:>14.2}",
+            player.uid, player.hands, player.ev, player.actual, player.luck
+        );
+    }
+    println!("hands: {}, skipped: {}", rsp.hands, rsp.skipped.len());
+    Ok(0)
+}
+
+fn verify(args: &Args) -> anyhow::Result<i32> {
+    let mut hands = vec![];
+    for hand in &args.positional {
+        if hand == "-" {
+            hands.push(None);
+            continue;
+        }
+        let cards = parse_cards(hand)?;
+        if cards.len() != 2 {
+            bail!("hand {} should have 2 cards", hand);
+        }
+        hands.push(Some([cards[0], cards[1]]));
+    }
+    let req = VerifyShuffleReq {
+        server_seed: args.option("server-seed")?.clone(),
+        commitment: args.option("commitment")?.clone(),
+        client_seed: args.option("client-seed")?.clone(),
+        nonce: args
+            .options
+            .get("nonce")
+            .map(|x| x.parse())
+            .transpose()?
+            .unwrap_or(0),
+        players: args.option("players")?.parse()?,
+        hands,
+        board: args.board()?,
+    };
+    let rsp = verify_shuffle(&req);
+    let code = if rsp.code == 0 && rsp.valid { 0 } else { 1 };
+    if args.json() {
+        println!("{}", serde_json::to_string_pretty(&rsp)?);
+        return Ok(code);
+    }
+    if rsp.code != 0 {
+        eprintln!("{}", rsp.msg);
+        return Ok(1);
+    }
+    for (seat, hands) in rsp.hands.iter().enumerate() {
+        println!("seat {:<4}{}", seat + 1, hand_string(hands));
+    }
+    println!("board     {}", rsp.board);
+    for mismatch in &rsp.mismatches {
+        println!("mismatch: {}", mismatch);
+    }
+    println!("{}", if rsp.valid { "valid" } else { "invalid" });
+    Ok(code)
+}
+
+fn push_fold(args: &Args) -> anyhow::Result<i32> {
+    let req = PushFoldReq {
+        players: args
+            .options
+            .get("players")
+            .map(|x| x.parse())
+            .transpose()?
+            .unwrap_or(2),
+        stack: args.option("stack")?.parse()?,
+        ante: args
+            .options
+            .get("ante")
+            .map(|x| x.parse())
+            .transpose()?
+            .unwrap_or(0.0),
+    };
+    let rsp = Evaluator {}.push_fold(req);
+    if args.json() {
+        println!("{}", serde_json::to_string_pretty(&rsp)?);
+        return Ok(if rsp.code == 0 { 0 } else { 1 });
+    }
+    if rsp.code != 0 {
+        eprintln!("{}", rsp.msg);
+        return Ok(1);
+    }
+    for chart in &rsp.charts {
+        let title = match &chart.vs {
+            Some(vs) => format!("{} call vs {}", chart.position, vs),
+            None => format!("{} push", chart.position),
+        };
+        println!("{} ({})", title, percent(chart.percent));
+        // 频率不低于一半的格子显示牌名，其余显示点
+        let ranks: Vec<char> = "AKQJT98765432".chars().collect();
+        for (row, cells) in chart.grid.iter().enumerate() {
+            let line: Vec<String> = cells
+                .iter()
+                .enumerate()
+                .map(|(col, freq)| {
+                    if *freq < 5000 {
+                        return format!("{:<4}", ".");
+                    }
+                    let (high, low) = (ranks[row.min(col)], ranks[row.max(col)]);
+                    let suffix = match row.cmp(&col) {
+                        std::cmp::Ordering::Less => "s",
+                        std::cmp::Ordering::Greater => "o",
+                        std::cmp::Ordering::Equal => "",
+                    };
+                    format!("{:<4}", format!("{}{}{}", high, low, suffix))
+                })
+                .collect();
+            println!("{}", line.concat().trim_end());
+        }
+        println!();
+    }
+    Ok(0)
+}
+
+fn hand_string(cards: &[Card]) -> String {
+    cards.iter().map(|x| x.to_string()).collect()
+}
+
+// rate以10000为分母
+fn percent(rate: u64) -> String {
+    format!("{:.2}%", rate as f64 / 100.0)
+}
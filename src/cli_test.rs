@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod tests {
+    use crate::cli::{run, Args, VALUE_OPTIONS};
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed =
+            Args::parse(&args("AsKs --board 7h8h2c QdQc --exact --seed 7 --json")).unwrap();
+        assert_eq!(parsed.positional, vec!["AsKs", "QdQc"]);
+        assert_eq!(parsed.options["board"], "7h8h2c");
+        assert_eq!(parsed.options["seed"], "7");
+        assert!(parsed.flags.contains("exact") && parsed.flags.contains("json"));
+        // 带值的参数取下一个参数作为值，即使它以--开头
+        for name in VALUE_OPTIONS {
+            let parsed = Args::parse(&args(&format!("--{} --json", name))).unwrap();
+            assert_eq!(parsed.options[name], "--json");
+            assert!(parsed.flags.is_empty());
+            assert!(Args::parse(&args(&format!("--{}", name))).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_clients() {
+        let parsed = Args::parse(&args("AsKs QdQc")).unwrap();
+        let clients = parsed.clients().unwrap();
+        assert_eq!(clients[1].uid, "2");
+        assert!(Args::parse(&args("AsKsQd")).unwrap().clients().is_err());
+        assert!(Args::parse(&args("AsXx")).unwrap().clients().is_err());
+    }
+
+    #[test]
+    fn test_exit_code() {
+        // 参数错误返回2，计算失败返回1
+        assert_eq!(run(&[]), 2);
+        assert_eq!(run(&args("unknown")), 2);
+        assert_eq!(run(&args("help")), 0);
+        assert_eq!(run(&args("equity AsKs QdQc --board 7h8h2c --json")), 0);
+        assert_eq!(run(&args("equity AsKs QdQc --board 7h8h2c --seed x")), 2);
+        assert_eq!(run(&args("equity AsKs Zz")), 2);
+        assert_eq!(run(&args("equity AsKs AsQc --json")), 1);
+        assert_eq!(run(&args("equity AsKs QdQc --board")), 2);
+        assert_eq!(run(&args("eval AsKs")), 2);
+        assert_eq!(run(&args("eval AsKsQsJsTs")), 0);
+        assert_eq!(run(&args("outs AsKs QdQc --board 7h8h2c")), 0);
+        assert_eq!(run(&args("push-fold --players 3")), 2);
+        assert_eq!(run(&args("verify --players 2")), 2);
+    }
+}
@@ -1,22 +1,383 @@
-use actix_web::{get, post, web, Responder};
+use std::io::{self, BufReader, Read};
+use std::time::Duration;
 
-use crate::models::model::{
-    CalculateOutsReq, CalculateOutsRsp, CalculateRatingReq, CalculateRatingRsp,
+use actix_web::{get, post, web, HttpResponse, Responder};
+use futures_util::StreamExt;
+use serde::Serialize;
+
+use calculate::models::model::{
+    AnnotateHistoryReq, AnnotateHistoryRsp, CalculateOutsReq, CalculateOutsRsp, CalculateRatingReq,
+    CalculateRatingRsp, CalculateTimelineReq, CalculateTimelineRsp, THREAD_LOCAL_DATA,
+};
+use calculate::models::model::{
+    BoardTextureReq, BoardTextureRsp, ChopRsp, DrawsReq, DrawsRsp, HandStrengthReq,
+    HandStrengthRsp, HiLoOutsReq, HiLoOutsRsp, HiLoRatingReq, HiLoRatingRsp, IcmCallReq,
+    IcmCallRsp, IcmReq, IcmRsp, NutRankReq, NutRankRsp, PotOddsReq, PotOddsRsp, PromotionReq,
+    PromotionRsp, PushFoldReq, PushFoldRsp, RabbitHuntReq, RabbitHuntRsp, ShuffleBoardReq,
+    ShuffleBoardRsp, ShuffleCommitRsp, ShuffleDealReq, ShuffleDealRsp, ShuffleRevealReq,
+    ShuffleRevealRsp, StudRatingReq, StudRatingRsp, VerifyShuffleReq, VerifyShuffleRsp,
 };
-use crate::services::evaluator::{CalculateRating, Evaluator};
+use calculate::services::evaluator::Evaluator;
+use calculate::services::shuffle;
+
+// 流式接口的计算时间上限
+const STREAM_BUDGET: Duration = Duration::from_secs(30);
 
 // 计算胜率
 #[post("/v1/calculate_rating")]
 pub async fn submit(req: web::Json<CalculateRatingReq>) -> web::Json<CalculateRatingRsp> {
-    let evaluator = Evaluator {};
-    let rsp: CalculateRatingRsp = evaluator.calculate_rating(req.into_inner()).await;
+    let trace_id = THREAD_LOCAL_DATA.with_borrow(|v| *v);
+    let rsp: CalculateRatingRsp = tokio::task::spawn_blocking(move || {
+        THREAD_LOCAL_DATA.set(trace_id);
+        let evaluator = Evaluator {};
+        evaluator.rating(req.into_inner())
+    })
+    .await
+    .unwrap();
     return web::Json(rsp);
 }
 
+// 以Server-Sent Events推送胜率的中间结果(progress)和最终结果(result)
+#[post("/v1/calculate_rating/stream")]
+pub async fn submit_stream(req: web::Json<CalculateRatingReq>) -> HttpResponse {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<web::Bytes>();
+    let trace_id = THREAD_LOCAL_DATA.with_borrow(|v| *v);
+    tokio::task::spawn_blocking(move || {
+        THREAD_LOCAL_DATA.set(trace_id);
+        let evaluator = Evaluator {};
+        let rsp =
+            evaluator.calculate_rating_progress(req.into_inner(), STREAM_BUDGET, &mut |progress| {
+                // 客户端断开后响应流被丢弃，发送失败时停止计算
+                tx.send(sse_event("progress", &progress)).is_ok()
+            });
+        let _ = tx.send(sse_event("result", &rsp));
+    });
+    let body = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv()
+            .await
+            .map(|bytes| (Ok::<web::Bytes, actix_web::Error>(bytes), rx))
+    });
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(body)
+}
+
+fn sse_event<T: Serialize>(event: &str, data: &T) -> web::Bytes {
+    let data = serde_json::to_string(data).unwrap();
+    web::Bytes::from(format!("event: {}\ndata: {}\n\n", event, data))
+}
+
 #[post("/v1/calculate_outs")]
 pub async fn calculate_outs(req: web::Json<CalculateOutsReq>) -> web::Json<CalculateOutsRsp> {
+    let trace_id = THREAD_LOCAL_DATA.with_borrow(|v| *v);
+    let rsp: CalculateOutsRsp = tokio::task::spawn_blocking(move || {
+        THREAD_LOCAL_DATA.set(trace_id);
+        let evaluator = Evaluator {};
+        evaluator.outs(req.into_inner())
+    })
+    .await
+    .unwrap();
+    return web::Json(rsp);
+}
+
+// 逐街计算胜率和outs，用于牌局回放
+#[post("/v1/calculate_timeline")]
+pub async fn calculate_timeline(
+    req: web::Json<CalculateTimelineReq>,
+) -> web::Json<CalculateTimelineRsp> {
+    let trace_id = THREAD_LOCAL_DATA.with_borrow(|v| *v);
+    let rsp: CalculateTimelineRsp = tokio::task::spawn_blocking(move || {
+        THREAD_LOCAL_DATA.set(trace_id);
+        let evaluator = Evaluator {};
+        evaluator.timeline(req.into_inner())
+    })
+    .await
+    .unwrap();
+    return web::Json(rsp);
+}
+
+// 请求体为ndjson格式的全下记录，边上传边计算，每算完一手推送hand事件，最后推送result
+#[post("/v1/allin_ev/stream")]
+pub async fn allin_ev_stream(mut payload: web::Payload) -> HttpResponse {
+    let (chunk_tx, chunk_rx) =
+        tokio::sync::mpsc::channel::<Result<web::Bytes, String>>(UPLOAD_CHUNKS);
+    // Payload只能在当前worker上读取，读到的块交给计算线程，计算跟不上时暂停读取
+    actix_web::rt::spawn(async move {
+        while let Some(chunk) = payload.next().await {
+            let chunk = chunk.map_err(|err| err.to_string());
+            if chunk_tx.send(chunk).await.is_err() {
+                break;
+            }
+        }
+    });
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<web::Bytes>();
+    let trace_id = THREAD_LOCAL_DATA.with_borrow(|v| *v);
+    tokio::task::spawn_blocking(move || {
+        THREAD_LOCAL_DATA.set(trace_id);
+        let evaluator = Evaluator {};
+        let reader = BufReader::new(ChunkReader {
+            rx: chunk_rx,
+            chunk: web::Bytes::new(),
+        });
+        let rsp = evaluator.allin_ev_report(reader, &mut |hand| {
+            let _ = tx.send(sse_event("hand", hand));
+        });
+        let _ = tx.send(sse_event("result", &rsp));
+    });
+    let body = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv()
+            .await
+            .map(|bytes| (Ok::<web::Bytes, actix_web::Error>(bytes), rx))
+    });
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(body)
+}
+
+// 上传时最多缓存的请求体块数
+const UPLOAD_CHUNKS: usize = 16;
+
+// 把异步读到的请求体块转为阻塞的Read，供计算线程逐行读取
+struct ChunkReader {
+    rx: tokio::sync::mpsc::Receiver<Result<web::Bytes, String>>,
+    chunk: web::Bytes,
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.rx.blocking_recv() {
+                Some(Ok(chunk)) => self.chunk = chunk,
+                Some(Err(err)) => return Err(io::Error::other(err)),
+                None => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.chunk.len());
+        buf[..n].copy_from_slice(&self.chunk.split_to(n));
+        Ok(n)
+    }
+}
+
+// 导入牌局记录(json或PokerStars文本)，为全下和摊牌的牌局标注逐街胜率和outs
+#[post("/v1/hand_history/annotate")]
+pub async fn annotate_history(req: web::Json<AnnotateHistoryReq>) -> web::Json<AnnotateHistoryRsp> {
     let evaluator = Evaluator {};
-    let rsp: CalculateOutsRsp = evaluator.calculate_outs(req.into_inner()).await;
+    let rsp: AnnotateHistoryRsp = evaluator.annotate_history(req.into_inner());
+    return web::Json(rsp);
+}
+
+// 牌局提前结束时补发剩余公共牌，看谁会赢
+#[post("/v1/rabbit_hunt")]
+pub async fn rabbit_hunt(req: web::Json<RabbitHuntReq>) -> web::Json<RabbitHuntRsp> {
+    let evaluator = Evaluator {};
+    let rsp: RabbitHuntRsp = evaluator.rabbit_hunt(req.into_inner());
+    return web::Json(rsp);
+}
+
+// 判定完整牌局是否达成坏牌奖和大牌奖，以及各玩家的奖金份额
+#[post("/v1/promotion")]
+pub async fn promotion(req: web::Json<PromotionReq>) -> web::Json<PromotionRsp> {
+    let evaluator = Evaluator {};
+    let rsp: PromotionRsp = evaluator.promotion(req.into_inner());
+    return web::Json(rsp);
+}
+
+// 手牌强度HS、PPOT、NPOT和EHS，对手为随机手牌或指定范围
+#[post("/v1/hand_strength")]
+pub async fn hand_strength(req: web::Json<HandStrengthReq>) -> web::Json<HandStrengthRsp> {
+    let evaluator = Evaluator {};
+    let rsp: HandStrengthRsp = evaluator.hand_strength(req.into_inner());
+    return web::Json(rsp);
+}
+
+// 翻牌和转牌时每个玩家的听牌类型和outs
+#[post("/v1/draws")]
+pub async fn draws(req: web::Json<DrawsReq>) -> web::Json<DrawsRsp> {
+    let evaluator = Evaluator {};
+    let rsp: DrawsRsp = evaluator.draws(req.into_inner());
+    return web::Json(rsp);
+}
+
+// 公共牌结构：成对、同花、连张、可能的顺子和当前最大的牌力
+#[post("/v1/board_texture")]
+pub async fn board_texture(req: web::Json<BoardTextureReq>) -> web::Json<BoardTextureRsp> {
+    let evaluator = Evaluator {};
+    let rsp: BoardTextureRsp = evaluator.board_texture(req.into_inner());
+    return web::Json(rsp);
+}
+
+// 一手牌在所有可能手牌中的排名，如第三坚果
+#[post("/v1/nut_rank")]
+pub async fn nut_rank(req: web::Json<NutRankReq>) -> web::Json<NutRankRsp> {
+    let evaluator = Evaluator {};
+    let rsp: NutRankRsp = evaluator.nut_rank(req.into_inner());
+    return web::Json(rsp);
+}
+
+// 跟注决策：需要的胜率、实际胜率、跟注EV和诈唬需要的弃牌率
+#[post("/v1/pot_odds")]
+pub async fn pot_odds(req: web::Json<PotOddsReq>) -> web::Json<PotOddsRsp> {
+    let trace_id = THREAD_LOCAL_DATA.with_borrow(|v| *v);
+    let rsp: PotOddsRsp = tokio::task::spawn_blocking(move || {
+        THREAD_LOCAL_DATA.set(trace_id);
+        let evaluator = Evaluator {};
+        evaluator.pot_odds(req.into_inner())
+    })
+    .await
+    .unwrap();
+    return web::Json(rsp);
+}
+
+// 锦标赛ICM奖金权益
+#[post("/v1/icm")]
+pub async fn icm(req: web::Json<IcmReq>) -> web::Json<IcmRsp> {
+    let trace_id = THREAD_LOCAL_DATA.with_borrow(|v| *v);
+    let rsp: IcmRsp = tokio::task::spawn_blocking(move || {
+        THREAD_LOCAL_DATA.set(trace_id);
+        let evaluator = Evaluator {};
+        evaluator.icm(req.into_inner())
+    })
+    .await
+    .unwrap();
+    return web::Json(rsp);
+}
+
+// 按ICM和按筹码分奖金的方案
+#[post("/v1/icm/chop")]
+pub async fn icm_chop(req: web::Json<IcmReq>) -> web::Json<ChopRsp> {
+    let trace_id = THREAD_LOCAL_DATA.with_borrow(|v| *v);
+    let rsp: ChopRsp = tokio::task::spawn_blocking(move || {
+        THREAD_LOCAL_DATA.set(trace_id);
+        let evaluator = Evaluator {};
+        evaluator.icm_chop(req.into_inner())
+    })
+    .await
+    .unwrap();
+    return web::Json(rsp);
+}
+
+// 锦标赛中面对全下时按ICM比较跟注和弃牌
+#[post("/v1/icm/call")]
+pub async fn icm_call(req: web::Json<IcmCallReq>) -> web::Json<IcmCallRsp> {
+    let trace_id = THREAD_LOCAL_DATA.with_borrow(|v| *v);
+    let rsp: IcmCallRsp = tokio::task::spawn_blocking(move || {
+        THREAD_LOCAL_DATA.set(trace_id);
+        let evaluator = Evaluator {};
+        evaluator.icm_call(req.into_inner())
+    })
+    .await
+    .unwrap();
+    return web::Json(rsp);
+}
+
+// 短筹码全下或弃牌的纳什均衡图表，首次计算较慢，超时后仍会算完并写入磁盘缓存
+#[post("/v1/push_fold")]
+pub async fn push_fold(req: web::Json<PushFoldReq>) -> web::Json<PushFoldRsp> {
+    let trace_id = THREAD_LOCAL_DATA.with_borrow(|v| *v);
+    let rsp: PushFoldRsp = tokio::task::spawn_blocking(move || {
+        THREAD_LOCAL_DATA.set(trace_id);
+        let evaluator = Evaluator {};
+        evaluator.push_fold(req.into_inner())
+    })
+    .await
+    .unwrap();
+    return web::Json(rsp);
+}
+
+// 高低牌分池玩法的胜率和分池统计
+#[post("/v1/hi_lo/calculate_rating")]
+pub async fn hi_lo_rating(req: web::Json<HiLoRatingReq>) -> web::Json<HiLoRatingRsp> {
+    let trace_id = THREAD_LOCAL_DATA.with_borrow(|v| *v);
+    let rsp: HiLoRatingRsp = tokio::task::spawn_blocking(move || {
+        THREAD_LOCAL_DATA.set(trace_id);
+        let evaluator = Evaluator {};
+        evaluator.hi_lo_rating(req.into_inner())
+    })
+    .await
+    .unwrap();
+    return web::Json(rsp);
+}
+
+// 高低牌分池玩法下一张牌的高牌、低牌和独得底池的outs
+#[post("/v1/hi_lo/calculate_outs")]
+pub async fn hi_lo_outs(req: web::Json<HiLoOutsReq>) -> web::Json<HiLoOutsRsp> {
+    let evaluator = Evaluator {};
+    let rsp: HiLoOutsRsp = evaluator.hi_lo_outs(req.into_inner());
+    return web::Json(rsp);
+}
+
+// 七张梅花、高低8和Razz的胜率
+#[post("/v1/stud/calculate_rating")]
+pub async fn stud_rating(req: web::Json<StudRatingReq>) -> web::Json<StudRatingRsp> {
+    let trace_id = THREAD_LOCAL_DATA.with_borrow(|v| *v);
+    let rsp: StudRatingRsp = tokio::task::spawn_blocking(move || {
+        THREAD_LOCAL_DATA.set(trace_id);
+        let evaluator = Evaluator {};
+        evaluator.stud_rating(req.into_inner())
+    })
+    .await
+    .unwrap();
+    return web::Json(rsp);
+}
+
+// 可验证公平的洗牌: commit公布种子承诺，deal用玩家种子发手牌，board发公共牌，reveal在牌局结束后公开种子
+#[post("/v1/shuffle/commit")]
+pub async fn shuffle_commit() -> web::Json<ShuffleCommitRsp> {
+    let trace_id = THREAD_LOCAL_DATA.with_borrow(|v| *v);
+    // 读写种子文件，不占用worker线程
+    let rsp: ShuffleCommitRsp = web::block(move || {
+        THREAD_LOCAL_DATA.set(trace_id);
+        shuffle::shuffle_commit()
+    })
+    .await
+    .unwrap();
+    return web::Json(rsp);
+}
+
+#[post("/v1/shuffle/deal")]
+pub async fn shuffle_deal(req: web::Json<ShuffleDealReq>) -> web::Json<ShuffleDealRsp> {
+    let trace_id = THREAD_LOCAL_DATA.with_borrow(|v| *v);
+    // 读写种子文件，不占用worker线程
+    let rsp: ShuffleDealRsp = web::block(move || {
+        THREAD_LOCAL_DATA.set(trace_id);
+        shuffle::shuffle_deal(req.into_inner())
+    })
+    .await
+    .unwrap();
+    return web::Json(rsp);
+}
+
+#[post("/v1/shuffle/board")]
+pub async fn shuffle_board(req: web::Json<ShuffleBoardReq>) -> web::Json<ShuffleBoardRsp> {
+    let trace_id = THREAD_LOCAL_DATA.with_borrow(|v| *v);
+    // 读写种子文件，不占用worker线程
+    let rsp: ShuffleBoardRsp = web::block(move || {
+        THREAD_LOCAL_DATA.set(trace_id);
+        shuffle::shuffle_board(req.into_inner())
+    })
+    .await
+    .unwrap();
+    return web::Json(rsp);
+}
+
+#[post("/v1/shuffle/reveal")]
+pub async fn shuffle_reveal(req: web::Json<ShuffleRevealReq>) -> web::Json<ShuffleRevealRsp> {
+    let trace_id = THREAD_LOCAL_DATA.with_borrow(|v| *v);
+    // 读写种子文件，不占用worker线程
+    let rsp: ShuffleRevealRsp = web::block(move || {
+        THREAD_LOCAL_DATA.set(trace_id);
+        shuffle::shuffle_reveal(req.into_inner())
+    })
+    .await
+    .unwrap();
+    return web::Json(rsp);
+}
+
+#[post("/v1/shuffle/verify")]
+pub async fn shuffle_verify(req: web::Json<VerifyShuffleReq>) -> web::Json<VerifyShuffleRsp> {
+    let rsp: VerifyShuffleRsp = shuffle::verify_shuffle(&req);
     return web::Json(rsp);
 }
 
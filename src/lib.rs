@@ -0,0 +1,7 @@
+// 胜率和outs计算库，HTTP服务(main.rs)和其他服务共用
+pub mod models;
+pub mod services;
+pub mod utils;
+
+pub use holdem_hand_evaluator::Hand;
+pub use services::evaluator::{CalculateRating, Evaluator};
@@ -2,8 +2,6 @@ use std::collections::HashMap;
 use std::time::Duration;
 use std::{env, panic};
 
-use crate::models::model::THREAD_LOCAL_DATA;
-use crate::utils::log::{log_error_debug, log_info_debug, log_info_display};
 use actix_http;
 use actix_http::body;
 use actix_web::body::MessageBody;
@@ -12,13 +10,16 @@ use actix_web::middleware::{from_fn, Next};
 use actix_web::web::Query;
 use actix_web::{dev, web, App, Error, HttpServer};
 use anyhow::anyhow;
+use calculate::models::model::THREAD_LOCAL_DATA;
+use calculate::services::push_fold;
+use calculate::services::shuffle;
+use calculate::utils::log::{log_error_debug, log_info_debug, log_info_display};
 use flexi_logger::{Age, Cleanup, Criterion, Duplicate, FileSpec, Naming, WriteMode};
 use uuid::Uuid;
 
+mod cli;
+mod cli_test;
 mod handlers;
-mod models;
-mod services;
-mod utils;
 
 fn panic_hook() {
     panic::set_hook(Box::new(|e| {
@@ -32,8 +33,10 @@ fn bytes_to_payload(buf: web::Bytes) -> dev::Payload {
     dev::Payload::from(pl)
 }
 
+// 上传大文件的流式接口，不读取和记录请求体
+const UPLOAD_PATHS: [&str; 1] = ["/v1/allin_ev/stream"];
+
 async fn mutate_body_type_with_extractors(
-    string_body: String,
     query: Query<HashMap<String, String>>,
     mut req: ServiceRequest,
     next: Next<impl MessageBody + 'static>,
@@ -41,10 +44,18 @@ async fn mutate_body_type_with_extractors(
     let my_uuid = Uuid::new_v4();
     THREAD_LOCAL_DATA.set(my_uuid);
     log_info_display("req url", req.uri());
-    log_info_display("req body is", &string_body);
+    if !UPLOAD_PATHS.contains(&req.path()) {
+        let string_body = req.extract::<String>().await?;
+        log_info_display("req body is", &string_body);
+        req.set_payload(bytes_to_payload(web::Bytes::from(string_body)));
+    }
     log_info_debug("req query string", &query);
-    req.set_payload(bytes_to_payload(web::Bytes::from(string_body)));
+    // 流式接口不缓存响应体
+    let streaming = req.path().ends_with("/stream");
     let res = next.call(req).await?;
+    if streaming {
+        return Ok(res.map_into_boxed_body());
+    }
     let (req, res) = res.into_parts();
     let (empty_rsp, rsp_body) = res.into_parts();
     let rsp_body_bytes = body::to_bytes(rsp_body).await.ok().unwrap();
@@ -54,11 +65,16 @@ async fn mutate_body_type_with_extractors(
     );
     let new_rsp = empty_rsp.set_body(rsp_body_bytes);
     let service_rsp = ServiceResponse::new(req, new_rsp);
-    Ok(service_rsp)
+    Ok(service_rsp.map_into_boxed_body())
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    // 带参数时作为命令行工具运行
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
     panic_hook();
     let mut log_dir = "";
     match env::var("PROFILE") {
@@ -89,13 +105,38 @@ async fn main() -> std::io::Result<()> {
         .start()
         .expect("error");
     // test();
+    push_fold::warm_up();
+    shuffle::start_sweeper();
     HttpServer::new(|| {
         App::new()
             .wrap(from_fn(mutate_body_type_with_extractors))
             .wrap(from_fn(timeout_2secs))
             .service(handlers::controller::submit)
+            .service(handlers::controller::submit_stream)
             .service(handlers::controller::hello)
             .service(handlers::controller::calculate_outs)
+            .service(handlers::controller::calculate_timeline)
+            .service(handlers::controller::annotate_history)
+            .service(handlers::controller::allin_ev_stream)
+            .service(handlers::controller::rabbit_hunt)
+            .service(handlers::controller::promotion)
+            .service(handlers::controller::hand_strength)
+            .service(handlers::controller::draws)
+            .service(handlers::controller::board_texture)
+            .service(handlers::controller::nut_rank)
+            .service(handlers::controller::pot_odds)
+            .service(handlers::controller::icm)
+            .service(handlers::controller::icm_chop)
+            .service(handlers::controller::icm_call)
+            .service(handlers::controller::push_fold)
+            .service(handlers::controller::hi_lo_rating)
+            .service(handlers::controller::hi_lo_outs)
+            .service(handlers::controller::stud_rating)
+            .service(handlers::controller::shuffle_commit)
+            .service(handlers::controller::shuffle_deal)
+            .service(handlers::controller::shuffle_board)
+            .service(handlers::controller::shuffle_reveal)
+            .service(handlers::controller::shuffle_verify)
     })
     .client_request_timeout(Duration::from_secs(1))
     .bind(("0.0.0.0", 8090))?
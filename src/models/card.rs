@@ -0,0 +1,486 @@
+use std::fmt;
+use std::str::FromStr;
+
+use holdem_hand_evaluator::Hand;
+use serde::de::{Error, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+const RANK_CHARS: [char; 13] = [
+    '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K', 'A',
+];
+const SUIT_CHARS: [char; 4] = ['s', 'h', 'c', 'd'];
+// 花色符号，实心和空心都接受，输出时使用实心
+const SUIT_SYMBOLS: [[char; 2]; 4] = [['♠', '♤'], ['♥', '♡'], ['♣', '♧'], ['♦', '♢']];
+
+/// 一副牌的张数
+pub const DECK_SIZE: usize = 52;
+
+/// 公共牌的最大张数
+pub const MAX_BOARD: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rank {
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+    Ace,
+}
+
+impl Rank {
+    pub const ALL: [Rank; 13] = [
+        Rank::Two,
+        Rank::Three,
+        Rank::Four,
+        Rank::Five,
+        Rank::Six,
+        Rank::Seven,
+        Rank::Eight,
+        Rank::Nine,
+        Rank::Ten,
+        Rank::Jack,
+        Rank::Queen,
+        Rank::King,
+        Rank::Ace,
+    ];
+
+    // 0为2，12为A
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn to_char(self) -> char {
+        RANK_CHARS[self.index()]
+    }
+
+    pub fn from_char(c: char) -> Option<Rank> {
+        RANK_CHARS
+            .iter()
+            .position(|x| *x == c)
+            .map(|i| Rank::ALL[i])
+    }
+
+    // 不区分大小写，10也作为T
+    pub fn parse(s: &str) -> Option<Rank> {
+        if s == "10" {
+            return Some(Rank::Ten);
+        }
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Rank::from_char(c.to_ascii_uppercase()),
+            _ => None,
+        }
+    }
+}
+
+// 顺序与CARDS、CARDSSTRING一致
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Suit {
+    Spade,
+    Heart,
+    Club,
+    Diamond,
+}
+
+impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Spade, Suit::Heart, Suit::Club, Suit::Diamond];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn to_char(self) -> char {
+        SUIT_CHARS[self.index()]
+    }
+
+    pub fn from_char(c: char) -> Option<Suit> {
+        SUIT_CHARS
+            .iter()
+            .position(|x| *x == c)
+            .map(|i| Suit::ALL[i])
+    }
+
+    pub fn to_symbol(self) -> char {
+        SUIT_SYMBOLS[self.index()][0]
+    }
+
+    // 不区分大小写，也接受花色符号
+    pub fn parse(c: char) -> Option<Suit> {
+        Suit::from_char(c.to_ascii_lowercase()).or_else(|| {
+            SUIT_SYMBOLS
+                .iter()
+                .position(|x| x.contains(&c))
+                .map(|i| Suit::ALL[i])
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseCardError(pub String);
+
+impl fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid cards: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseCardError {}
+
+/// 单张牌，id = 4 * rank + suit，与CARDS、CARDSSTRING的下标以及Hand::add_card的参数一致
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Card(u8);
+
+impl Card {
+    pub fn new(rank: Rank, suit: Suit) -> Card {
+        Card((rank.index() * 4 + suit.index()) as u8)
+    }
+
+    pub fn from_id(id: usize) -> Option<Card> {
+        if id < DECK_SIZE {
+            Some(Card(id as u8))
+        } else {
+            None
+        }
+    }
+
+    pub fn id(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn rank(self) -> Rank {
+        Rank::ALL[self.id() / 4]
+    }
+
+    pub fn suit(self) -> Suit {
+        Suit::ALL[self.id() % 4]
+    }
+
+    // 全部52张牌，按id排序
+    pub fn all() -> impl Iterator<Item = Card> {
+        (0..DECK_SIZE).map(|id| Card(id as u8))
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.rank().to_char(), self.suit().to_char())
+    }
+}
+
+// 接受"Ah"、"AH"、"ah"、"10h"、"A♥"以及0-51的id
+impl FromStr for Card {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) {
+            return s
+                .parse::<usize>()
+                .ok()
+                .and_then(Card::from_id)
+                .ok_or_else(|| ParseCardError(s.to_string()));
+        }
+        let Some(suit) = s.chars().last() else {
+            return Err(ParseCardError(s.to_string()));
+        };
+        let rank = &s[..s.len() - suit.len_utf8()];
+        match (Rank::parse(rank), Suit::parse(suit)) {
+            (Some(rank), Some(suit)) => Ok(Card::new(rank, suit)),
+            _ => Err(ParseCardError(s.to_string())),
+        }
+    }
+}
+
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+struct CardVisitor;
+
+impl Visitor<'_> for CardVisitor {
+    type Value = Card;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a card such as \"Ah\", \"10h\", \"A♥\" or an id between 0 and 51")
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Card, E> {
+        v.parse().map_err(E::custom)
+    }
+
+    fn visit_u64<E: Error>(self, v: u64) -> Result<Card, E> {
+        usize::try_from(v)
+            .ok()
+            .and_then(Card::from_id)
+            .ok_or_else(|| E::custom(ParseCardError(v.to_string())))
+    }
+
+    fn visit_i64<E: Error>(self, v: i64) -> Result<Card, E> {
+        match u64::try_from(v) {
+            Ok(v) => self.visit_u64(v),
+            Err(_) => Err(E::custom(ParseCardError(v.to_string()))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(CardVisitor)
+    }
+}
+
+// 解析连在一起的多张牌，如"AsKd7h"、"10h J♥"，空白和逗号作为分隔
+pub fn parse_cards(s: &str) -> Result<Vec<Card>, ParseCardError> {
+    let chars: Vec<char> = s
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != ',')
+        .collect();
+    let mut cards = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        // 10占两个字符
+        let rank_len = if chars[i] == '1' && chars.get(i + 1) == Some(&'0') {
+            2
+        } else {
+            1
+        };
+        if i + rank_len >= chars.len() {
+            return Err(ParseCardError(s.to_string()));
+        }
+        let card: String = chars[i..i + rank_len + 1].iter().collect();
+        cards.push(card.parse()?);
+        i += rank_len + 1;
+    }
+    Ok(cards)
+}
+
+/// 输出牌的记法
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CardNotation {
+    #[default]
+    Short, // "Ah"
+    Upper,  // "AH"
+    Symbol, // "A♥"
+    Id,     // 49，与Card::id一致
+}
+
+// 按指定记法输出的牌
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum NotatedCard {
+    Id(u8),
+    Text(String),
+}
+
+impl CardNotation {
+    pub fn format(self, card: Card) -> NotatedCard {
+        match self {
+            CardNotation::Short => NotatedCard::Text(card.to_string()),
+            CardNotation::Upper => NotatedCard::Text(card.to_string().to_uppercase()),
+            CardNotation::Symbol => NotatedCard::Text(format!(
+                "{}{}",
+                card.rank().to_char(),
+                card.suit().to_symbol()
+            )),
+            CardNotation::Id => NotatedCard::Id(card.id() as u8),
+        }
+    }
+}
+
+/// 牌的集合，第id位表示对应的牌
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CardSet(u64);
+
+impl CardSet {
+    pub fn new() -> CardSet {
+        CardSet(0)
+    }
+
+    pub fn full() -> CardSet {
+        CardSet((1u64 << DECK_SIZE) - 1)
+    }
+
+    pub fn mask(&self) -> u64 {
+        self.0
+    }
+
+    pub fn contains(&self, card: Card) -> bool {
+        self.0 & (1u64 << card.id()) != 0
+    }
+
+    // 返回false表示已经存在
+    pub fn insert(&mut self, card: Card) -> bool {
+        let exist = self.contains(card);
+        self.0 |= 1u64 << card.id();
+        !exist
+    }
+
+    pub fn remove(&mut self, card: Card) {
+        self.0 &= !(1u64 << card.id());
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(&self, other: CardSet) -> CardSet {
+        CardSet(self.0 | other.0)
+    }
+
+    // 不在集合中的牌
+    pub fn complement(&self) -> CardSet {
+        CardSet(!self.0 & CardSet::full().0)
+    }
+
+    // 按id从小到大
+    pub fn iter(&self) -> impl Iterator<Item = Card> {
+        let mut mask = self.0;
+        std::iter::from_fn(move || {
+            if mask == 0 {
+                return None;
+            }
+            let id = mask.trailing_zeros() as usize;
+            mask &= mask - 1;
+            Some(Card(id as u8))
+        })
+    }
+
+    pub fn hand(&self) -> Hand {
+        self.iter()
+            .fold(Hand::new(), |hand, card| hand.add_card(card.id()))
+    }
+}
+
+impl Extend<Card> for CardSet {
+    fn extend<T: IntoIterator<Item = Card>>(&mut self, iter: T) {
+        for card in iter {
+            self.insert(card);
+        }
+    }
+}
+
+impl FromIterator<Card> for CardSet {
+    fn from_iter<T: IntoIterator<Item = Card>>(iter: T) -> Self {
+        let mut set = CardSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+/// 公共牌，最多5张且不重复
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct Board(Vec<Card>);
+
+impl Board {
+    pub fn new(cards: Vec<Card>) -> Result<Board, ParseCardError> {
+        if cards.len() > MAX_BOARD {
+            return Err(ParseCardError(format!(
+                "board has {} cards, more than {}",
+                cards.len(),
+                MAX_BOARD
+            )));
+        }
+        if cards.iter().copied().collect::<CardSet>().len() != cards.len() {
+            return Err(ParseCardError("board has duplicate cards".to_string()));
+        }
+        Ok(Board(cards))
+    }
+
+    pub fn cards(&self) -> &[Card] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // 前n张公共牌，用于逐街计算
+    pub fn prefix(&self, n: usize) -> Board {
+        Board(self.0[..n.min(self.0.len())].to_vec())
+    }
+
+    pub fn card_set(&self) -> CardSet {
+        self.0.iter().copied().collect()
+    }
+
+    pub fn hand(&self) -> Hand {
+        self.card_set().hand()
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for card in &self.0 {
+            write!(f, "{}", card)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Board {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Board::new(parse_cards(s)?)
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let cards = Vec::<Card>::deserialize(deserializer)?;
+        Board::new(cards).map_err(D::Error::custom)
+    }
+}
+
+// 牌型，Hand::evaluate的结果右移12位即为牌型
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandCategory {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+}
+
+impl HandCategory {
+    pub const ALL: [HandCategory; 9] = [
+        HandCategory::HighCard,
+        HandCategory::OnePair,
+        HandCategory::TwoPair,
+        HandCategory::ThreeOfAKind,
+        HandCategory::Straight,
+        HandCategory::Flush,
+        HandCategory::FullHouse,
+        HandCategory::FourOfAKind,
+        HandCategory::StraightFlush,
+    ];
+
+    pub fn of(value: u16) -> HandCategory {
+        HandCategory::ALL[(value >> 12) as usize]
+    }
+}
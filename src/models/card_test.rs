@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::card::{
+        parse_cards, Board, Card, CardNotation, CardSet, NotatedCard, Rank, Suit,
+    };
+    use crate::models::model::CalculateRatingReq;
+
+    #[test]
+    fn test_parse_card() {
+        let card: Card = "Ah".parse().unwrap();
+        assert_eq!(card, Card::new(Rank::Ace, Suit::Heart));
+        // 与CARDSSTRING的下标一致
+        assert_eq!(card.id(), 49);
+        assert_eq!(card.to_string(), "Ah");
+        assert!("Ahh".parse::<Card>().is_err());
+        assert!("1h".parse::<Card>().is_err());
+        assert!("".parse::<Card>().is_err());
+    }
+
+    #[test]
+    fn test_parse_alternative_notations() {
+        let ah: Card = "Ah".parse().unwrap();
+        for s in ["AH", "ah", "A♥", "a♡", "49"] {
+            assert_eq!(s.parse::<Card>().unwrap(), ah);
+        }
+        assert_eq!("10h".parse::<Card>().unwrap().to_string(), "Th");
+        assert!("52".parse::<Card>().is_err());
+        let cards = parse_cards("10hJ♥ qh,Kh").unwrap();
+        let cards: Vec<String> = cards.iter().map(|x| x.to_string()).collect();
+        assert_eq!(cards, vec!["Th", "Jh", "Qh", "Kh"]);
+        let json: Vec<Card> = serde_json::from_str(r#"["AH", 49, "10♥"]"#).unwrap();
+        assert_eq!(json, vec![ah, ah, "Th".parse().unwrap()]);
+        assert!(serde_json::from_str::<Card>("52").is_err());
+        assert_eq!(
+            CardNotation::Symbol.format(ah),
+            NotatedCard::Text("A♥".to_string())
+        );
+        assert_eq!(
+            CardNotation::Upper.format(ah),
+            NotatedCard::Text("AH".to_string())
+        );
+        assert_eq!(CardNotation::Id.format(ah), NotatedCard::Id(49));
+    }
+
+    #[test]
+    fn test_card_set() {
+        let set: CardSet = ["As", "2c", "Td"]
+            .iter()
+            .map(|x| x.parse().unwrap())
+            .collect();
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.complement().len(), 49);
+        let cards: Vec<String> = set.iter().map(|x| x.to_string()).collect();
+        assert_eq!(cards, vec!["2c", "Td", "As"]);
+    }
+
+    #[test]
+    fn test_deserialize_rejects_invalid_cards() {
+        let ok = r#"{"clients":[{"uid":"1","hands":["As","Ad"]},{"uid":"2","hands":["2s","Ts"]}],"deal_cards":["7h","8h","2c"]}"#;
+        let req: CalculateRatingReq = serde_json::from_str(ok).unwrap();
+        assert_eq!(req.deal_cards, "7h8h2c".parse::<Board>().unwrap());
+        let bad_card = r#"{"clients":[{"uid":"1","hands":["As","Ax"]}]}"#;
+        assert!(serde_json::from_str::<CalculateRatingReq>(bad_card).is_err());
+        let bad_board = r#"{"clients":[],"deal_cards":["7h","7h"]}"#;
+        assert!(serde_json::from_str::<CalculateRatingReq>(bad_board).is_err());
+    }
+}
@@ -1,2 +1,6 @@
+pub mod card;
+mod card_test;
 pub mod error_model;
-pub(crate) mod model;
+pub mod model;
+pub mod range;
+mod range_test;
@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use uuid::Uuid;
 
+use crate::models::card::{Board, Card, CardNotation, HandCategory, NotatedCard};
+
 // 定义一个线程本地变量，每个线程会有自己独立的 RefCell
 thread_local! {
     pub static THREAD_LOCAL_DATA: RefCell<Uuid> = RefCell::new(Uuid::new_v4());
@@ -14,6 +16,44 @@ impl CalculateOutsReq {
             clients: self.clients.clone(),
             deal_cards: self.deal_cards.clone(),
             dead_cards: self.dead_cards.clone(),
+            exact: false,
+            target_std_err: None,
+            time_budget_ms: None,
+            seed: None,
+            samples: None,
+            second_board: None,
+        };
+    }
+}
+
+impl RabbitHuntReq {
+    pub(crate) fn to_rating_req(&self) -> CalculateRatingReq {
+        return CalculateRatingReq {
+            clients: self.clients.clone(),
+            deal_cards: self.deal_cards.clone(),
+            dead_cards: self.dead_cards.clone(),
+            exact: false,
+            target_std_err: None,
+            time_budget_ms: None,
+            seed: None,
+            samples: None,
+            second_board: None,
+        };
+    }
+}
+
+impl CalculateTimelineReq {
+    pub(crate) fn to_street_req(&self, deal_len: usize) -> CalculateRatingReq {
+        return CalculateRatingReq {
+            clients: self.clients.clone(),
+            deal_cards: self.deal_cards.prefix(deal_len),
+            dead_cards: self.dead_cards.clone(),
+            exact: false,
+            target_std_err: None,
+            time_budget_ms: None,
+            seed: None,
+            samples: None,
+            second_board: None,
         };
     }
 }
@@ -21,19 +61,25 @@ impl CalculateOutsReq {
 #[derive(Deserialize, Serialize)]
 pub struct CalculateOutsReq {
     pub clients: Vec<UserCards>,
-    pub deal_cards: Vec<String>, // 公共牌
+    pub deal_cards: Board, // 公共牌
+    #[serde(default)]
+    pub dead_cards: Vec<Card>, // 已发过的不计算的牌
     #[serde(default)]
-    pub dead_cards: Vec<String>, // 已发过的不计算的牌
+    pub notation: CardNotation, // outs中牌的记法，默认"Ah"
+    #[serde(default)]
+    pub second_board: Option<Board>, // 双公共牌底池的第二组公共牌，张数与deal_cards相同
 }
 #[derive(Deserialize, Serialize)]
 pub struct CalculateOutsRsp {
     pub code: u32,
     pub outs: Vec<Outs>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub second_outs: Option<Vec<Outs>>, // 双公共牌时第二组公共牌的outs，outs为第一组的
     pub msg: String,
 }
 #[derive(Deserialize, Serialize)]
 pub struct Outs {
-    pub cards: Vec<String>,
+    pub cards: Vec<NotatedCard>,
     pub uid: String,
 }
 
@@ -41,26 +87,839 @@ pub struct Outs {
 pub struct CalculateRatingReq {
     pub clients: Vec<UserCards>,
     #[serde(default)]
-    pub deal_cards: Vec<String>, // 公共牌
+    pub deal_cards: Board, // 公共牌
+    #[serde(default)]
+    pub dead_cards: Vec<Card>, // 已发过的不计算的牌
+    #[serde(default)]
+    pub exact: bool, // 剩余3张及以上公共牌时也穷举计算，默认随机法
+    #[serde(default)]
+    pub target_std_err: Option<u64>, // 随机法的目标标准误差，单位同rate，达到后停止采样
+    #[serde(default)]
+    pub time_budget_ms: Option<u64>, // 计算时间上限，不超过接口本身的上限
     #[serde(default)]
-    pub dead_cards: Vec<String>, // 已发过的不计算的牌
+    pub seed: Option<u64>, // 随机法的种子，相同的种子和采样次数得到相同的结果
+    #[serde(default)]
+    pub samples: Option<u64>, // 随机法固定的采样次数，传入返回的seed和samples可以复现按时间或精度停止的结果，仍受计算时间上限约束
+    #[serde(default)]
+    pub second_board: Option<Board>, // 双公共牌底池的第二组公共牌，张数与deal_cards相同
 }
 #[derive(Deserialize, Serialize)]
 pub struct CalculateRatingRsp {
     pub code: u32,
-    pub clients_rate: Vec<ClientRate>,
+    pub clients_rate: Vec<ClientRate>, // 双公共牌时rate为平均分到的底池份额
+    pub samples: u64,                  // 实际计算的公共牌组合数
+    pub seed: u64,                     // 本次计算使用的随机种子，未指定时随机生成
+    #[serde(default)]
+    pub completed: bool, // 是否算完，超时或客户端断开提前停止时为false
+    #[serde(default)]
+    pub exact: bool, // 是否穷举了全部公共牌组合，为true时结果是精确值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub double_board: Option<Vec<DoubleBoardRate>>,
     pub msg: String,
 }
+// 双公共牌底池每组公共牌各分一半，以下都以10000为分母
+#[derive(Deserialize, Serialize)]
+pub struct DoubleBoardRate {
+    pub uid: String,
+    pub first: u64,   // 第一组公共牌的胜率，平局按份额计
+    pub second: u64,  // 第二组公共牌的胜率
+    pub scoop: u64,   // 两组都独赢
+    pub win_one: u64, // 只独赢其中一组
+    pub chop: u64,    // 没有独赢但至少平分一组
+}
 #[derive(Deserialize, Serialize)]
 pub struct ClientRate {
     pub uid: String,
-    pub rate: u64, // 1000为分母
+    pub rate: u64, // 胜率，平局算一半，10000为分母；双公共牌时为两组的平均值
+    // 期望分得的底池份额，平局时由打平的玩家均分，单位同rate；两人时与rate相同
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub share: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ci_low: Option<u64>, // 95%置信区间下限，单位同rate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ci_high: Option<u64>, // 95%置信区间上限，单位同rate
+}
+
+// 流式计算胜率时推送的中间结果
+#[derive(Deserialize, Serialize)]
+pub struct RatingProgress {
+    pub samples: u64,    // 已计算的公共牌组合数
+    pub population: u64, // 穷举时的组合总数，随机法为0
+    pub clients_rate: Vec<ClientRate>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct CalculateTimelineReq {
+    pub clients: Vec<UserCards>,
+    pub deal_cards: Board, // 完整的5张公共牌
+    #[serde(default)]
+    pub dead_cards: Vec<Card>, // 已发过的不计算的牌
+    #[serde(default)]
+    pub notation: CardNotation, // outs中牌的记法，默认"Ah"
+}
+#[derive(Deserialize, Serialize)]
+pub struct CalculateTimelineRsp {
+    pub code: u32,
+    pub streets: Vec<StreetRate>,
+    pub msg: String,
+}
+#[derive(Deserialize, Serialize)]
+pub struct StreetRate {
+    pub street: String,    // preflop/flop/turn/river
+    pub deal_cards: Board, // 该街的公共牌
+    pub clients_rate: Vec<ClientRate>,
+    pub outs: Vec<Outs>, // 仅flop和turn有outs
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Street {
+    Preflop,
+    Flop,
+    Turn,
+    River,
+}
+
+// 牌局记录，我们自己的导出格式，PokerStars文本解析后也转为此格式
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct HandHistory {
+    pub hand_id: String,
+    pub players: Vec<HistoryPlayer>,
+    #[serde(default)]
+    pub board: Board, // 最终的公共牌
+    #[serde(default)]
+    pub all_in_street: Option<Street>, // 第一次有玩家全下的街
+}
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct HistoryPlayer {
+    pub uid: String,
+    #[serde(default)]
+    pub hands: Option<[Card; 2]>, // 未亮牌时为空
+    #[serde(default)]
+    pub all_in: bool,
+    #[serde(default)]
+    pub showdown: bool,
+    #[serde(default)]
+    pub folded: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct AnnotateHistoryReq {
+    #[serde(default)]
+    pub hands: Vec<HandHistory>, // json格式的牌局
+    #[serde(default)]
+    pub pokerstars: String, // PokerStars文本格式的牌局，可以包含多手
+    #[serde(default)]
+    pub notation: CardNotation,
+}
+#[derive(Deserialize, Serialize)]
+pub struct AnnotateHistoryRsp {
+    pub code: u32,
+    pub hands: Vec<AnnotatedHand>,
+    pub skipped: Vec<SkippedHand>, // 没有全下或摊牌的牌局
+    pub msg: String,
+}
+#[derive(Deserialize, Serialize)]
+pub struct AnnotatedHand {
+    pub hand_id: String,
+    pub situation: String, // all_in/showdown
+    pub all_in_street: Option<Street>,
+    pub clients: Vec<UserCards>,
+    pub board: Board,
+    pub streets: Vec<StreetRate>,
+}
+#[derive(Deserialize, Serialize)]
+pub struct SkippedHand {
+    pub hand_id: String,
+    pub reason: String,
+}
+
+// 一手全下的记录，会话报告按行(ndjson)读取
+#[derive(Deserialize, Serialize, Clone)]
+pub struct AllInHand {
+    pub hand_id: String,
+    #[serde(default)]
+    pub deal_cards: Board, // 全下时的公共牌
+    #[serde(default)]
+    pub dead_cards: Vec<Card>,
+    pub players: Vec<AllInPlayer>,
+    #[serde(default)]
+    pub rake: u64, // 从底池抽取的佣金，按比例从每个池中扣除
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+#[derive(Deserialize, Serialize, Clone)]
+pub struct AllInPlayer {
+    pub uid: String,
+    #[serde(default)]
+    pub hands: Option<[Card; 2]>, // 弃牌的玩家可以没有手牌
+    pub invested: u64, // 本手投入的全部筹码，用于切分边池
+    #[serde(default)]
+    pub folded: bool,
+    #[serde(default)]
+    pub won: u64, // 实际从底池赢回的筹码
+}
+// 单手的期望和实际输赢，cum_开头的字段是会话累计值，用于画全下EV曲线
+#[derive(Deserialize, Serialize)]
+pub struct HandEv {
+    pub hand_id: String,
+    pub players: Vec<PlayerHandEv>,
+}
+#[derive(Deserialize, Serialize)]
+pub struct PlayerHandEv {
+    pub uid: String,
+    pub ev: f64,     // 全下时的期望净赢
+    pub actual: i64, // 实际净赢
+    pub cum_ev: f64,
+    pub cum_actual: i64,
+}
+#[derive(Deserialize, Serialize)]
+pub struct AllInEvRsp {
+    pub code: u32,
+    pub hands: u64,
+    pub players: Vec<PlayerEv>,
+    pub skipped: Vec<SkippedHand>, // 无法计算的手，解析失败时hand_id为行号
+    pub msg: String,
+}
+#[derive(Deserialize, Serialize)]
+pub struct PlayerEv {
+    pub uid: String,
+    pub hands: u64,
+    pub ev: f64,
+    pub actual: i64,
+    pub luck: f64, // actual - ev，正数表示运气好
+}
+
+// 牌局在河牌前结束时，看剩余的公共牌会是什么
+#[derive(Deserialize, Serialize)]
+pub struct RabbitHuntReq {
+    pub clients: Vec<UserCards>,
+    #[serde(default)]
+    pub deal_cards: Board, // 已发的公共牌
+    #[serde(default)]
+    pub dead_cards: Vec<Card>,
+    #[serde(default)]
+    pub deck: Vec<Card>, // 剩余牌堆的顺序，从上往下发，与shuffle二选一
+    #[serde(default)]
+    pub burn: bool, // deck中每街发牌前先烧一张
+    #[serde(default)]
+    pub shuffle: Option<ShuffleSeeds>, // 已公开的发牌种子，由此推出实际会发的公共牌
+}
+// 与/v1/shuffle/verify相同的种子，按shuffle_deck洗牌、deal发牌
+#[derive(Deserialize, Serialize)]
+pub struct ShuffleSeeds {
+    pub server_seed: String,
+    pub client_seed: String,
+    #[serde(default)]
+    pub nonce: u64,
+    pub players: usize,
+}
+#[derive(Deserialize, Serialize)]
+pub struct RabbitHuntRsp {
+    pub code: u32,
+    pub rabbit_cards: Vec<Card>, // 补发的公共牌
+    pub deal_cards: Board,       // 补齐后的5张公共牌
+    pub clients: Vec<ShowdownHand>,
+    pub winners: Vec<String>, // 平局时有多个uid
+    pub msg: String,
+}
+#[derive(Deserialize, Serialize)]
+pub struct ShowdownHand {
+    pub uid: String,
+    pub category: HandCategory,
+    pub value: u16, // 牌力，越大越好
+}
+
+// 完整牌局的坏牌奖和大牌奖资格判定
+#[derive(Deserialize, Serialize)]
+pub struct PromotionReq {
+    pub clients: Vec<UserCards>, // 摊牌的玩家
+    pub deal_cards: Board,       // 完整的5张公共牌
+    #[serde(default)]
+    pub table: Vec<String>, // 其他在座但未摊牌的玩家uid，分享桌上份额
+    pub rules: PromotionRules,
+}
+#[derive(Deserialize, Serialize, Default)]
+pub struct PromotionRules {
+    #[serde(default)]
+    pub bad_beat: Option<BadBeatRule>,
+    #[serde(default)]
+    pub high_hand: Option<HighHandRule>,
+}
+// 份额以10000为分母
+#[derive(Deserialize, Serialize)]
+pub struct BadBeatRule {
+    #[serde(default = "default_bad_beat_category")]
+    pub min_category: HandCategory, // 输家的最小牌型，默认四条
+    #[serde(default)]
+    pub min_hand: Option<Vec<Card>>, // 输家的最小5张牌，比牌型更细的门槛，如8888
+    #[serde(default = "default_true")]
+    pub both_hole_cards_play: bool, // 输家的两张手牌都要用上
+    #[serde(default)]
+    pub winner_hole_cards_play: bool, // 赢家的两张手牌也要用上
+    #[serde(default = "default_loser_share")]
+    pub loser_share: u64,
+    #[serde(default = "default_winner_share")]
+    pub winner_share: u64,
+    #[serde(default = "default_table_share")]
+    pub table_share: u64, // 桌上其余玩家平分
+}
+#[derive(Deserialize, Serialize)]
+pub struct HighHandRule {
+    #[serde(default = "default_high_hand_category")]
+    pub min_category: HandCategory, // 默认四条
+    #[serde(default)]
+    pub min_hand: Option<Vec<Card>>,
+    #[serde(default = "default_true")]
+    pub both_hole_cards_play: bool,
+}
+fn default_bad_beat_category() -> HandCategory {
+    HandCategory::FourOfAKind
+}
+fn default_high_hand_category() -> HandCategory {
+    HandCategory::FourOfAKind
+}
+fn default_true() -> bool {
+    true
+}
+fn default_loser_share() -> u64 {
+    5000
+}
+fn default_winner_share() -> u64 {
+    2500
+}
+fn default_table_share() -> u64 {
+    2500
+}
+#[derive(Deserialize, Serialize)]
+pub struct PromotionRsp {
+    pub code: u32,
+    pub bad_beat: Option<BadBeatResult>, // 未达成时为空
+    pub high_hands: Vec<QualifiedHand>,  // 达成大牌奖的玩家，牌力大的在前
+    pub msg: String,
+}
+#[derive(Deserialize, Serialize)]
+pub struct BadBeatResult {
+    pub loser: QualifiedHand,
+    pub winners: Vec<QualifiedHand>,
+    pub shares: Vec<PromotionShare>,
+}
+#[derive(Deserialize, Serialize, Clone)]
+pub struct QualifiedHand {
+    pub uid: String,
+    pub category: HandCategory,
+    pub value: u16,
+    pub best_five: [Card; 5], // 用上两张手牌的最大5张牌优先
+    pub both_hole_cards_play: bool,
+}
+#[derive(Deserialize, Serialize)]
+pub struct PromotionShare {
+    pub uid: String,
+    pub role: String, // loser/winner/table
+    pub share: u64,   // 10000为分母
+}
+
+// 手牌强度，概率都以10000为分母
+#[derive(Deserialize, Serialize)]
+pub struct HandStrengthReq {
+    pub hands: [Card; 2],
+    pub deal_cards: Board, // 3到5张公共牌
+    #[serde(default)]
+    pub dead_cards: Vec<Card>,
+    #[serde(default)]
+    pub range: Option<String>, // 对手范围，如"QQ+,AKs,AsQs"，为空时为随机手牌
+    #[serde(default = "default_opponents")]
+    pub opponents: u32, // 对手人数，HS按人数取幂
+}
+fn default_opponents() -> u32 {
+    1
+}
+#[derive(Deserialize, Serialize)]
+pub struct HandStrengthRsp {
+    pub code: u32,
+    pub hs: u64,      // 当前牌力领先的概率，平局算一半
+    pub ppot: u64,    // 当前落后或平局，发完公共牌后反超的概率
+    pub npot: u64,    // 当前领先或平局，发完公共牌后被反超的概率
+    pub ehs: u64,     // HS + (1 - HS) * PPOT
+    pub ehs_net: u64, // HS * (1 - NPOT) + (1 - HS) * PPOT
+    pub combos: u64,  // 去掉已知牌后对手范围的组合数
+    pub msg: String,
+}
+
+// 翻牌和转牌时每个玩家的听牌类型及对应的outs
+#[derive(Deserialize, Serialize)]
+pub struct DrawsReq {
+    pub clients: Vec<UserCards>,
+    pub deal_cards: Board, // 3或4张公共牌
+    #[serde(default)]
+    pub dead_cards: Vec<Card>,
+    #[serde(default)]
+    pub notation: CardNotation,
+}
+#[derive(Deserialize, Serialize)]
+pub struct DrawsRsp {
+    pub code: u32,
+    pub clients: Vec<ClientDraws>,
+    pub msg: String,
+}
+#[derive(Deserialize, Serialize)]
+pub struct ClientDraws {
+    pub uid: String,
+    pub draws: Vec<Draw>,
+    pub outs: Vec<NotatedCard>, // 全部听牌outs的并集
+}
+#[derive(Deserialize, Serialize)]
+pub struct Draw {
+    pub kind: DrawKind,
+    pub outs: Vec<NotatedCard>, // 后门听牌为补上后成为听牌的牌
+}
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DrawKind {
+    FlushDraw,
+    NutFlushDraw,
+    OpenEnded,
+    Gutshot,
+    DoubleGutshot,
+    BackdoorFlush,
+    BackdoorStraight,
+    Overcards,
+    ComboDraw, // 同花听牌加顺子听牌
+}
+
+// 公共牌结构，翻牌、转牌、河牌
+#[derive(Deserialize, Serialize)]
+pub struct BoardTextureReq {
+    pub deal_cards: Board, // 3到5张公共牌
+    #[serde(default = "default_top")]
+    pub top: usize, // 返回牌力最大的前N种
+    #[serde(default)]
+    pub notation: CardNotation,
+}
+fn default_top() -> usize {
+    5
+}
+#[derive(Deserialize, Serialize)]
+pub struct BoardTextureRsp {
+    pub code: u32,
+    pub street: Option<Street>,
+    pub pairing: BoardPairing,
+    pub suits: SuitTexture,
+    pub flush_possible: bool,      // 公共牌有3张同花
+    pub flush_draw_possible: bool, // 翻牌和转牌有2张同花，还没有3张同花
+    pub connectedness: u32,        // 任意连续5个点数中公共牌最多占几个，3及以上可能成顺
+    pub straights: Vec<PossibleStraight>,
+    pub top_hands: Vec<BoardHand>, // 第一个为当前的坚果牌
+    pub msg: String,
+}
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BoardPairing {
+    Unpaired,
+    Paired,
+    TwoPair,
+    Trips,
+    FullHouse,
+    Quads,
+}
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SuitTexture {
+    Rainbow,  // 没有两张同花
+    TwoTone,  // 有同花但不是全部同花
+    Monotone, // 全部同一花色
+}
+#[derive(Deserialize, Serialize)]
+pub struct PossibleStraight {
+    pub high: String,       // 顺子最大的点数，如"9"，A2345为"5"
+    pub needs: Vec<String>, // 需要的手牌点数，公共牌已成顺时为空
+}
+#[derive(Deserialize, Serialize)]
+pub struct BoardHand {
+    pub category: HandCategory,
+    pub value: u16,
+    pub combos: u32,
+    pub hands: Vec<[NotatedCard; 2]>, // 组成该牌力的全部手牌组合
+}
+
+// 一手牌在所有可能手牌中的排名
+#[derive(Deserialize, Serialize)]
+pub struct NutRankReq {
+    pub hands: [Card; 2],
+    pub deal_cards: Board, // 3到5张公共牌
+    #[serde(default)]
+    pub dead_cards: Vec<Card>,
+}
+#[derive(Deserialize, Serialize)]
+pub struct NutRankRsp {
+    pub code: u32,
+    pub category: HandCategory,
+    pub value: u16,
+    pub rank: u32, // 1为坚果，3为第三坚果，牌力相同的组合算同一名
+    pub beat: u32, // 比这手牌大的组合数
+    pub tie: u32,
+    pub lose: u32,
+    pub combos: u32, // 去掉已知牌后的全部组合数
+    pub msg: String,
+}
+
+// 跟注决策：底池赔率、实际胜率和跟注EV
+#[derive(Deserialize, Serialize)]
+pub struct PotOddsReq {
+    pub hands: [Card; 2],
+    pub opponents: Vec<Opponent>,
+    #[serde(default)]
+    pub deal_cards: Board,
+    #[serde(default)]
+    pub dead_cards: Vec<Card>,
+    pub pot: u64,     // 对手下注前的底池
+    pub to_call: u64, // 需要跟注的筹码
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+// 对手的手牌已知时填hands，否则填range，如"QQ+,AKs"
+#[derive(Deserialize, Serialize)]
+pub struct Opponent {
+    #[serde(default)]
+    pub hands: Option<[Card; 2]>,
+    #[serde(default)]
+    pub range: Option<String>,
+}
+#[derive(Deserialize, Serialize)]
+pub struct PotOddsRsp {
+    pub code: u32,
+    pub required_equity: u64,      // 跟注不亏需要的胜率，10000为分母
+    pub equity: u64,               // 实际胜率，平局算一半，10000为分母
+    pub call_ev: f64,              // 跟注相对弃牌的筹码EV
+    pub bluff_fold_frequency: u64, // 以同样大小下注诈唬时对手需要弃牌的比例，10000为分母
+    pub samples: u64,              // 实际采样次数，范围的组合多次冲突而放弃的采样不计入
+    pub seed: u64,
+    pub msg: String,
+}
+
+// ICM锦标赛奖金权益，payouts按名次从第一名开始
+#[derive(Deserialize, Serialize)]
+pub struct IcmReq {
+    pub players: Vec<IcmPlayer>,
+    pub payouts: Vec<u64>,
+    #[serde(default)]
+    pub method: IcmMethod,
+    #[serde(default)]
+    pub samples: Option<u64>, // 随机法的采样次数
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+#[derive(Deserialize, Serialize, Clone)]
+pub struct IcmPlayer {
+    pub uid: String,
+    pub stack: u64,
+}
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IcmMethod {
+    #[default]
+    Auto, // 计算量不大时精确计算，否则随机法
+    Exact,      // Malmuth–Harville精确计算
+    MonteCarlo, // 按筹码加权随机抽取名次
+}
+#[derive(Deserialize, Serialize)]
+pub struct IcmRsp {
+    pub code: u32,
+    pub players: Vec<IcmEquity>,
+    pub method: IcmMethod, // 实际使用的方法
+    pub samples: u64,      // 随机法的采样次数，精确计算为0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    pub msg: String,
+}
+#[derive(Deserialize, Serialize)]
+pub struct IcmEquity {
+    pub uid: String,
+    pub stack: u64,
+    pub equity: f64, // 奖金权益
+    pub share: u64,  // 占总奖金的比例，10000为分母
+}
+// 分奖金的方案，金额为整数且总和等于总奖金
+#[derive(Deserialize, Serialize)]
+pub struct ChopRsp {
+    pub code: u32,
+    pub players: Vec<ChopShare>,
+    pub method: IcmMethod,
+    pub msg: String,
+}
+#[derive(Deserialize, Serialize)]
+pub struct ChopShare {
+    pub uid: String,
+    pub stack: u64,
+    pub icm: u64,       // 按ICM权益分
+    pub chip_chop: u64, // 每人先得剩余的最低奖金，其余按筹码比例分
+}
+
+// 锦标赛中面对全下时按ICM比较跟注和弃牌
+#[derive(Deserialize, Serialize)]
+pub struct IcmCallReq {
+    pub players: Vec<IcmPlayer>, // 全下前桌上所有玩家身前剩余的筹码，已扣除各自下的盲注和前注
+    pub payouts: Vec<u64>,
+    pub shover: IcmHand,
+    pub caller: IcmHand,
+    #[serde(default)]
+    pub dead_money: u64, // 全下前底池中已有的筹码，包括双方自己下的盲注和前注
+    #[serde(default)]
+    pub deal_cards: Board,
+    #[serde(default)]
+    pub dead_cards: Vec<Card>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+// 至少一方的手牌已知，另一方可以是范围
+#[derive(Deserialize, Serialize)]
+pub struct IcmHand {
+    pub uid: String,
+    #[serde(default)]
+    pub hands: Option<[Card; 2]>,
+    #[serde(default)]
+    pub range: Option<String>,
+}
+#[derive(Deserialize, Serialize)]
+pub struct IcmCallRsp {
+    pub code: u32,
+    pub equity: u64,               // 跟注方摊牌的胜率，10000为分母
+    pub required_equity: u64,      // 按ICM跟注不亏需要的胜率
+    pub chip_required_equity: u64, // 按筹码跟注不亏需要的胜率
+    pub should_call: bool,
+    pub players: Vec<IcmDecisionEv>,
+    pub msg: String,
+}
+#[derive(Deserialize, Serialize)]
+pub struct IcmDecisionEv {
+    pub uid: String,
+    pub fold: f64, // 跟注方弃牌时的奖金权益
+    pub call: f64, // 跟注方跟注时的奖金权益
+}
+
+// 短筹码全下或弃牌的纳什均衡，筹码和前注以大盲为单位
+#[derive(Deserialize, Serialize)]
+pub struct PushFoldReq {
+    #[serde(default = "default_push_fold_players")]
+    pub players: usize,
+    pub stack: f64, // 有效筹码，所有玩家相同
+    #[serde(default)]
+    pub ante: f64, // 每人的前注
+}
+fn default_push_fold_players() -> usize {
+    2
+}
+#[derive(Deserialize, Serialize)]
+pub struct PushFoldRsp {
+    pub code: u32,
+    pub charts: Vec<PushFoldChart>,
+    pub cached: bool, // 结果来自磁盘缓存
+    pub msg: String,
+}
+#[derive(Deserialize, Serialize, Clone)]
+pub struct PushFoldChart {
+    pub position: String,
+    pub action: PushFoldAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vs: Option<String>, // 跟注时全下的位置
+    pub percent: u64,        // 占全部1326种组合的比例，10000为分母
+    pub range: String,       // 频率不低于一半的起手牌，如"AA,AKs,AKo"
+    pub grid: Vec<Vec<u64>>, // 13x13的频率，10000为分母，行列从A到2，右上为同花，左下为不同花
+}
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PushFoldAction {
+    Push,
+    Call,
+}
+
+// 高低牌分池的玩法，低牌需要5张不同的8或以下(A算最小)，没有低牌时高牌赢得整个底池
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HiLoGame {
+    #[default]
+    OmahaHiLo, // 4张手牌，高牌和低牌都必须用2张手牌和3张公共牌
+    HoldemHiLo, // 2张手牌，7张中任选5张
+}
+#[derive(Deserialize, Serialize, Clone)]
+pub struct HiLoUserCards {
+    pub hands: Vec<Card>,
+    pub uid: String,
+}
+#[derive(Deserialize, Serialize)]
+pub struct HiLoRatingReq {
+    #[serde(default)]
+    pub game: HiLoGame,
+    pub clients: Vec<HiLoUserCards>,
+    #[serde(default)]
+    pub deal_cards: Board,
+    #[serde(default)]
+    pub dead_cards: Vec<Card>,
+    #[serde(default)]
+    pub seed: Option<u64>, // 剩余3张及以上公共牌时随机法的种子
+}
+#[derive(Deserialize, Serialize)]
+pub struct HiLoRatingRsp {
+    pub code: u32,
+    pub clients_rate: Vec<HiLoClientRate>,
+    pub low_rate: u64, // 有人成低牌的比例
+    pub samples: u64,
+    pub seed: u64,
+    pub msg: String,
+}
+// 以下都以10000为分母
+#[derive(Deserialize, Serialize)]
+pub struct HiLoClientRate {
+    pub uid: String,
+    pub rate: u64,      // 平均分到的底池份额
+    pub scoop: u64,     // 独得整个底池
+    pub high_only: u64, // 只赢或平分高牌
+    pub low_only: u64,  // 只赢或平分低牌
+    pub quartered: u64, // 只分到四分之一底池
+}
+#[derive(Deserialize, Serialize)]
+pub struct HiLoOutsReq {
+    #[serde(default)]
+    pub game: HiLoGame,
+    pub clients: Vec<HiLoUserCards>,
+    pub deal_cards: Board, // 3或4张公共牌
+    #[serde(default)]
+    pub dead_cards: Vec<Card>,
+    #[serde(default)]
+    pub notation: CardNotation,
+}
+#[derive(Deserialize, Serialize)]
+pub struct HiLoOutsRsp {
+    pub code: u32,
+    pub outs: Vec<HiLoOuts>,
+    pub msg: String,
+}
+// 下一张牌发出后赢或平分高牌、低牌以及独得底池的牌
+#[derive(Deserialize, Serialize)]
+pub struct HiLoOuts {
+    pub uid: String,
+    pub high: Vec<NotatedCard>,
+    pub low: Vec<NotatedCard>,
+    pub scoop: Vec<NotatedCard>,
+}
+
+// 七张梅花类玩法，没有公共牌，每人最多3张暗牌和4张明牌，7张中任选5张
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StudGame {
+    #[default]
+    Stud, // 比高牌
+    Stud8, // 高低8分池，规则与高低牌分池相同
+    Razz,  // 比A-5低牌，顺子和同花不算，对子算
+}
+#[derive(Deserialize, Serialize, Clone)]
+pub struct StudPlayer {
+    pub uid: String,
+    #[serde(default)]
+    pub up_cards: Vec<Card>, // 明牌
+    #[serde(default)]
+    pub down_cards: Vec<Card>, // 已知的暗牌，未知的暗牌随机发
+}
+#[derive(Deserialize, Serialize)]
+pub struct StudRatingReq {
+    #[serde(default)]
+    pub game: StudGame,
+    pub clients: Vec<StudPlayer>,
+    #[serde(default)]
+    pub dead_cards: Vec<Card>, // 已弃牌玩家的明牌等看到过的牌
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+// 字段含义与高低牌分池相同，七张梅花整个底池按高牌统计，Razz整个底池按低牌统计
+#[derive(Deserialize, Serialize)]
+pub struct StudRatingRsp {
+    pub code: u32,
+    pub clients_rate: Vec<HiLoClientRate>,
+    pub low_rate: u64,
+    pub samples: u64,
+    pub seed: u64,
+    pub msg: String,
+}
+
+// 可验证公平的洗牌：发牌前公布服务端种子的sha256承诺，牌局结束后公开种子
+#[derive(Deserialize, Serialize)]
+pub struct ShuffleCommitRsp {
+    pub code: u32,
+    pub hand_id: String,
+    pub commitment: String, // sha256(server_seed)的十六进制
+    pub token: String,      // 只给游戏服务，发牌和公开种子时需要
+    pub msg: String,
+}
+#[derive(Deserialize, Serialize)]
+pub struct ShuffleDealReq {
+    pub hand_id: String,
+    pub token: String,
+    pub client_seed: String, // 玩家提供的种子
+    #[serde(default)]
+    pub nonce: u64,
+    pub players: usize,
+    pub seat: usize, // 座位从1开始，只返回这个座位的手牌
+}
+#[derive(Deserialize, Serialize)]
+pub struct ShuffleDealRsp {
+    pub code: u32,
+    pub seat: usize,
+    pub hands: Option<[Card; 2]>,
+    pub msg: String,
+}
+#[derive(Deserialize, Serialize)]
+pub struct ShuffleBoardReq {
+    pub hand_id: String,
+    pub token: String,
+    pub street: Street, // 只返回到这条街为止的公共牌
+}
+#[derive(Deserialize, Serialize)]
+pub struct ShuffleBoardRsp {
+    pub code: u32,
+    pub board: Board,
+    pub msg: String,
+}
+#[derive(Deserialize, Serialize)]
+pub struct ShuffleRevealReq {
+    pub hand_id: String,
+    pub token: String,
+}
+#[derive(Deserialize, Serialize)]
+pub struct ShuffleRevealRsp {
+    pub code: u32,
+    pub server_seed: String,
+    pub commitment: String,
+    pub client_seed: String,
+    pub nonce: u64,
+    pub msg: String,
+}
+#[derive(Deserialize, Serialize)]
+pub struct VerifyShuffleReq {
+    pub server_seed: String,
+    pub commitment: String,
+    pub client_seed: String,
+    #[serde(default)]
+    pub nonce: u64,
+    pub players: usize,
+    #[serde(default)]
+    pub hands: Vec<Option<[Card; 2]>>, // 按座位顺序，未知的座位为空
+    #[serde(default)]
+    pub board: Board, // 已发出的公共牌，可以不完整
+}
+#[derive(Deserialize, Serialize)]
+pub struct VerifyShuffleRsp {
+    pub code: u32,
+    pub valid: bool,
+    pub mismatches: Vec<String>, // 不一致的地方
+    pub hands: Vec<[Card; 2]>,   // 由种子推出的手牌
+    pub board: Board,
+    pub msg: String,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
 pub struct UserCards {
-    pub hands: [String; 2], // 手牌
-    pub uid: String,        // 用户uid
+    pub hands: [Card; 2], // 手牌
+    pub uid: String,      // 用户uid
 }
 
 #[derive(Deserialize, Serialize)]
@@ -0,0 +1,236 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::models::card::{parse_cards, Card, CardSet, ParseCardError, Rank, Suit};
+
+// 起手牌的类别，对子6种组合，同花4种，不同花12种
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ClassKind {
+    Pair,
+    Suited,
+    Offsuit,
+}
+
+/// 起手牌类别，如AA、AKs、AKo，high不小于low
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct HandClass {
+    pub high: Rank,
+    pub low: Rank,
+    pub kind: ClassKind,
+}
+
+impl HandClass {
+    pub fn new(a: Rank, b: Rank, suited: bool) -> HandClass {
+        let (high, low) = if a >= b { (a, b) } else { (b, a) };
+        let kind = if high == low {
+            ClassKind::Pair
+        } else if suited {
+            ClassKind::Suited
+        } else {
+            ClassKind::Offsuit
+        };
+        HandClass { high, low, kind }
+    }
+
+    pub fn of(hands: [Card; 2]) -> HandClass {
+        HandClass::new(
+            hands[0].rank(),
+            hands[1].rank(),
+            hands[0].suit() == hands[1].suit(),
+        )
+    }
+
+    // 全部169种类别，按AA、AKs、AKo...22的顺序
+    pub fn all() -> Vec<HandClass> {
+        let mut classes = vec![];
+        for high in Rank::ALL.iter().rev() {
+            for low in Rank::ALL.iter().rev().filter(|x| *x <= high) {
+                if high == low {
+                    classes.push(HandClass::new(*high, *low, false));
+                } else {
+                    classes.push(HandClass::new(*high, *low, true));
+                    classes.push(HandClass::new(*high, *low, false));
+                }
+            }
+        }
+        classes
+    }
+
+    pub fn combos(&self) -> Vec<[Card; 2]> {
+        let mut combos = vec![];
+        for (i, a) in Suit::ALL.iter().enumerate() {
+            for (j, b) in Suit::ALL.iter().enumerate() {
+                let keep = match self.kind {
+                    ClassKind::Pair => i < j,
+                    ClassKind::Suited => i == j,
+                    ClassKind::Offsuit => i != j,
+                };
+                if keep {
+                    combos.push([Card::new(self.high, *a), Card::new(self.low, *b)]);
+                }
+            }
+        }
+        combos
+    }
+}
+
+impl fmt::Display for HandClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.high.to_char(), self.low.to_char())?;
+        match self.kind {
+            ClassKind::Pair => Ok(()),
+            ClassKind::Suited => write!(f, "s"),
+            ClassKind::Offsuit => write!(f, "o"),
+        }
+    }
+}
+
+/// 手牌范围，逗号分隔，如"QQ+,A2s+,KQo,T9s-65s,22-55,AsKs"，AK表示同花和不同花
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Range(Vec<[Card; 2]>);
+
+impl Range {
+    // 全部1326种两张牌的组合
+    pub fn full() -> Range {
+        let mut combos = vec![];
+        for a in Card::all() {
+            for b in Card::all().filter(|x| x.id() > a.id()) {
+                combos.push([b, a]);
+            }
+        }
+        Range(combos)
+    }
+
+    pub fn combos(&self) -> &[[Card; 2]] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // 去掉与已知牌冲突的组合
+    pub fn without(&self, known: CardSet) -> Range {
+        Range(
+            self.0
+                .iter()
+                .filter(|x| !known.contains(x[0]) && !known.contains(x[1]))
+                .copied()
+                .collect(),
+        )
+    }
+}
+
+impl FromStr for Range {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut seen = HashSet::new();
+        let mut combos = vec![];
+        for token in s.split(',').map(|x| x.trim()).filter(|x| !x.is_empty()) {
+            for combo in parse_token(token)? {
+                // 用两张牌的掩码去重
+                let mask: CardSet = combo.iter().copied().collect();
+                if seen.insert(mask.mask()) {
+                    combos.push(combo);
+                }
+            }
+        }
+        if combos.is_empty() {
+            return Err(ParseCardError(s.to_string()));
+        }
+        Ok(Range(combos))
+    }
+}
+
+fn parse_token(token: &str) -> Result<Vec<[Card; 2]>, ParseCardError> {
+    let err = || ParseCardError(token.to_string());
+    // 具体的两张牌，如AsKs，类别最多3个字符(AKs)，避免22被当作id
+    if token.chars().count() >= 4 && !token.contains('-') && !token.ends_with('+') {
+        let cards = parse_cards(token)?;
+        if cards.len() == 2 && cards[0] != cards[1] {
+            return Ok(vec![[cards[0], cards[1]]]);
+        }
+        return Err(err());
+    }
+    let mut classes = vec![];
+    if let Some((from, to)) = token.split_once('-') {
+        let from = parse_classes(from).ok_or_else(err)?;
+        let to = parse_classes(to).ok_or_else(err)?;
+        if from.len() != to.len() {
+            return Err(err());
+        }
+        for (a, b) in from.into_iter().zip(to) {
+            classes.extend(class_span(a, b).ok_or_else(err)?);
+        }
+    } else if let Some(class) = token.strip_suffix('+') {
+        for a in parse_classes(class).ok_or_else(err)? {
+            let top = if a.kind == ClassKind::Pair {
+                HandClass::new(Rank::Ace, Rank::Ace, false)
+            } else {
+                // 踢脚增加到比高牌小一级
+                HandClass {
+                    low: Rank::ALL[a.high.index() - 1],
+                    ..a
+                }
+            };
+            classes.extend(class_span(a, top).ok_or_else(err)?);
+        }
+    } else {
+        classes = parse_classes(token).ok_or_else(err)?;
+    }
+    Ok(classes.iter().flat_map(|x| x.combos()).collect())
+}
+
+// 解析AK、AKs、AKo、QQ，AK包含同花和不同花
+fn parse_classes(s: &str) -> Option<Vec<HandClass>> {
+    let chars: Vec<char> = s.chars().collect();
+    if chars.len() < 2 || chars.len() > 3 {
+        return None;
+    }
+    let a = Rank::parse(&chars[0].to_string())?;
+    let b = Rank::parse(&chars[1].to_string())?;
+    match chars.get(2).map(|x| x.to_ascii_lowercase()) {
+        None if a == b => Some(vec![HandClass::new(a, b, false)]),
+        None => Some(vec![
+            HandClass::new(a, b, true),
+            HandClass::new(a, b, false),
+        ]),
+        Some('s') if a != b => Some(vec![HandClass::new(a, b, true)]),
+        Some('o') if a != b => Some(vec![HandClass::new(a, b, false)]),
+        _ => None,
+    }
+}
+
+// 两个同类别之间的全部类别：对子按点数变化，高牌相同时踢脚变化，间隔相同时一起变化(如T9s-65s)
+fn class_span(a: HandClass, b: HandClass) -> Option<Vec<HandClass>> {
+    if a.kind != b.kind {
+        return None;
+    }
+    let gap = |x: HandClass| x.high.index() - x.low.index();
+    let between = |x: Rank, y: Rank| x.index().min(y.index())..=x.index().max(y.index());
+    let ranks: Vec<(usize, usize)> = if a.kind == ClassKind::Pair {
+        between(a.high, b.high).map(|i| (i, i)).collect()
+    } else if a.high == b.high {
+        between(a.low, b.low).map(|i| (a.high.index(), i)).collect()
+    } else if gap(a) == gap(b) {
+        between(a.low, b.low).map(|i| (i + gap(a), i)).collect()
+    } else {
+        return None;
+    };
+    Some(
+        ranks
+            .into_iter()
+            .map(|(high, low)| HandClass {
+                high: Rank::ALL[high],
+                low: Rank::ALL[low],
+                kind: a.kind,
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::card::{parse_cards, CardSet};
+    use crate::models::range::{HandClass, Range};
+
+    fn len(s: &str) -> usize {
+        s.parse::<Range>().unwrap().len()
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(len("AA"), 6);
+        assert_eq!(len("AKs"), 4);
+        assert_eq!(len("AKo"), 12);
+        assert_eq!(len("AK"), 16);
+        assert_eq!(len("QQ+"), 18);
+        assert_eq!(len("22-55"), 24);
+        assert_eq!(len("A2s+"), 48);
+        assert_eq!(len("T9s-65s"), 20);
+        assert_eq!(len("AsKs, ks as"), 1);
+        assert_eq!(len("QQ+,AK,AsKs"), 34);
+        assert_eq!(Range::full().len(), 1326);
+        for bad in ["", "AKx", "AAs", "AK-QJo", "AsAs", "T9s-63s"] {
+            assert!(bad.parse::<Range>().is_err(), "{}", bad);
+        }
+        let known: CardSet = parse_cards("AsKd").unwrap().into_iter().collect();
+        assert_eq!("AA,KK".parse::<Range>().unwrap().without(known).len(), 6);
+    }
+
+    #[test]
+    fn test_hand_class() {
+        let classes = HandClass::all();
+        assert_eq!(classes.len(), 169);
+        assert_eq!(classes[0].to_string(), "AA");
+        assert_eq!(classes[1].to_string(), "AKs");
+        assert_eq!(classes[168].to_string(), "22");
+        let total: usize = classes.iter().map(|x| x.combos().len()).sum();
+        assert_eq!(total, 1326);
+        let hands = parse_cards("7h9h").unwrap();
+        assert_eq!(HandClass::of([hands[0], hands[1]]).to_string(), "97s");
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use crate::models::error_model;
+use crate::models::model::{
+    AllInEvRsp, AllInHand, CalculateRatingReq, HandEv, PlayerEv, PlayerHandEv, SkippedHand,
+    UserCards,
+};
+use crate::services::evaluator::Evaluator;
+
+// 一个底池(主池或边池)及有资格赢得它的玩家下标
+#[derive(Debug, PartialEq)]
+pub struct Pot {
+    pub amount: u64,
+    pub eligible: Vec<usize>,
+}
+
+// 按未弃牌玩家的投入切分主池和边池，弃牌玩家的筹码留在对应的池里
+pub fn split_pots(hand: &AllInHand) -> Vec<Pot> {
+    let mut levels: Vec<u64> = hand
+        .players
+        .iter()
+        .filter(|x| !x.folded && x.invested > 0)
+        .map(|x| x.invested)
+        .collect();
+    levels.sort_unstable();
+    levels.dedup();
+    let mut pots: Vec<Pot> = vec![];
+    let mut prev = 0;
+    for level in levels {
+        let amount = hand
+            .players
+            .iter()
+            .map(|x| x.invested.min(level) - x.invested.min(prev))
+            .sum();
+        let eligible = (0..hand.players.len())
+            .filter(|&i| !hand.players[i].folded && hand.players[i].invested >= level)
+            .collect();
+        pots.push(Pot { amount, eligible });
+        prev = level;
+    }
+    // 弃牌玩家超出最高一层的筹码归入最后一个池
+    let rest: u64 = hand
+        .players
+        .iter()
+        .map(|x| x.invested - x.invested.min(prev))
+        .sum();
+    if let Some(last) = pots.last_mut() {
+        last.amount += rest;
+    }
+    return pots;
+}
+
+impl Evaluator {
+    // 全下时每个玩家的期望净赢，每个池按有资格玩家的胜率分配
+    pub fn hand_ev(&self, hand: &AllInHand) -> Result<Vec<f64>, String> {
+        let total: u64 = hand.players.iter().map(|x| x.invested).sum();
+        if total == 0 {
+            return Err("pot is empty".to_string());
+        }
+        if hand.rake > total {
+            return Err("rake is larger than pot".to_string());
+        }
+        // 弃牌玩家亮过的牌当作死牌
+        let mut dead_cards = hand.dead_cards.clone();
+        for player in hand.players.iter().filter(|x| x.folded) {
+            dead_cards.extend(player.hands.iter().flatten());
+        }
+        let rake_rate = 1.0 - hand.rake as f64 / total as f64;
+        let mut ev: Vec<f64> = hand.players.iter().map(|x| -(x.invested as f64)).collect();
+        for pot in split_pots(hand) {
+            let amount = pot.amount as f64 * rake_rate;
+            if pot.eligible.len() == 1 {
+                ev[pot.eligible[0]] += amount;
+                continue;
+            }
+            let mut clients = vec![];
+            for &i in &pot.eligible {
+                let player = &hand.players[i];
+                let Some(hands) = player.hands else {
+                    return Err(format!("hands of {} is unknown", player.uid));
+                };
+                clients.push(UserCards {
+                    hands,
+                    uid: player.uid.clone(),
+                });
+            }
+            let rating_rsp = self.rating(CalculateRatingReq {
+                clients,
+                deal_cards: hand.deal_cards.clone(),
+                dead_cards: dead_cards.clone(),
+                exact: false,
+                target_std_err: None,
+                time_budget_ms: None,
+                seed: hand.seed,
+                samples: None,
+                second_board: None,
+            });
+            if rating_rsp.code != 0 {
+                return Err(rating_rsp.msg);
+            }
+            // 按底池份额分配，平局均分；份额四舍五入后归一化，保证分完整个底池
+            let shares: Vec<f64> = rating_rsp
+                .clients_rate
+                .iter()
+                .map(|x| x.share.unwrap_or(0) as f64)
+                .collect();
+            let share_sum: f64 = shares.iter().sum();
+            if share_sum == 0.0 {
+                return Err("pot share is empty".to_string());
+            }
+            for (&i, share) in pot.eligible.iter().zip(shares.iter()) {
+                ev[i] += amount * share / share_sum;
+            }
+        }
+        return Ok(ev);
+    }
+
+    // 逐行读取ndjson格式的全下记录，每算完一手回调一次，内存占用与文件大小无关
+    pub fn allin_ev_report(
+        &self,
+        reader: impl BufRead,
+        on_hand: &mut dyn FnMut(&HandEv),
+    ) -> AllInEvRsp {
+        let mut rsp = AllInEvRsp {
+            code: 0,
+            hands: 0,
+            players: vec![],
+            skipped: vec![],
+            msg: "".to_string(),
+        };
+        let mut index_by_uid = HashMap::new();
+        for (n, line) in reader.lines().enumerate() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    rsp.code = error_model::ERROR_INVALID;
+                    rsp.msg = format!("read line {} failed: {}", n + 1, err);
+                    return rsp;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let hand: AllInHand = match serde_json::from_str(&line) {
+                Ok(hand) => hand,
+                Err(err) => {
+                    rsp.skipped.push(SkippedHand {
+                        hand_id: format!("line {}", n + 1),
+                        reason: err.to_string(),
+                    });
+                    continue;
+                }
+            };
+            let ev = match self.hand_ev(&hand) {
+                Ok(ev) => ev,
+                Err(reason) => {
+                    rsp.skipped.push(SkippedHand {
+                        hand_id: hand.hand_id,
+                        reason,
+                    });
+                    continue;
+                }
+            };
+            let mut hand_ev = HandEv {
+                hand_id: hand.hand_id.clone(),
+                players: vec![],
+            };
+            for (player, ev) in hand.players.iter().zip(ev) {
+                let index = *index_by_uid.entry(player.uid.clone()).or_insert_with(|| {
+                    rsp.players.push(PlayerEv {
+                        uid: player.uid.clone(),
+                        hands: 0,
+                        ev: 0.0,
+                        actual: 0,
+                        luck: 0.0,
+                    });
+                    rsp.players.len() - 1
+                });
+                let total = &mut rsp.players[index];
+                let actual = player.won as i64 - player.invested as i64;
+                total.hands += 1;
+                total.ev += ev;
+                total.actual += actual;
+                total.luck = total.actual as f64 - total.ev;
+                hand_ev.players.push(PlayerHandEv {
+                    uid: player.uid.clone(),
+                    ev,
+                    actual,
+                    cum_ev: total.ev,
+                    cum_actual: total.actual,
+                });
+            }
+            rsp.hands += 1;
+            on_hand(&hand_ev);
+        }
+        return rsp;
+    }
+}
@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::model::AllInHand;
+    use crate::services::allin_ev::{split_pots, Pot};
+    use crate::services::evaluator::Evaluator;
+
+    // A短码全下，B和C跟注后C在转牌弃牌，B河牌中K
+    const SIDE_POT: &str = r#"{"hand_id":"1","deal_cards":["Ah","Kh","7c","2d"],"players":[
+        {"uid":"A","hands":["As","Ad"],"invested":50,"won":0},
+        {"uid":"B","hands":["Ks","Kd"],"invested":200,"won":370},
+        {"uid":"C","invested":120,"folded":true}]}"#;
+
+    #[test]
+    fn test_split_pots() {
+        let hand: AllInHand = serde_json::from_str(SIDE_POT).unwrap();
+        let pots = split_pots(&hand);
+        assert_eq!(
+            pots,
+            vec![
+                Pot {
+                    amount: 150,
+                    eligible: vec![0, 1]
+                },
+                Pot {
+                    amount: 220,
+                    eligible: vec![1]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_allin_ev_report() {
+        let hand: AllInHand = serde_json::from_str(SIDE_POT).unwrap();
+        let river = r#"{"hand_id":"2","deal_cards":["Ah","Kh","7c","2d","3s"],"rake":10,"players":[
+            {"uid":"A","hands":["As","Ad"],"invested":100,"won":190},
+            {"uid":"B","hands":["Ks","Kd"],"invested":100,"won":0}]}"#;
+        let river: AllInHand = serde_json::from_str(river).unwrap();
+        // ndjson每行一手，空行跳过
+        let input = format!(
+            "{}\n\nnot json\n{}\n",
+            serde_json::to_string(&hand).unwrap(),
+            serde_json::to_string(&river).unwrap()
+        );
+        let mut hands = vec![];
+        let rsp = Evaluator {}.allin_ev_report(input.as_bytes(), &mut |hand| {
+            hands.push(hand.hand_id.clone());
+        });
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.hands, 2);
+        assert_eq!(hands, vec!["1", "2"]);
+        assert_eq!(rsp.skipped.len(), 1);
+        assert_eq!(rsp.skipped[0].hand_id, "line 3");
+
+        // 第一手只剩一张河牌，A的AA只输给K，胜率43/44
+        let a = &rsp.players[0];
+        let first = 150.0 * 43.0 / 44.0 - 50.0;
+        assert!((a.ev - (first + 90.0)).abs() < 1.0);
+        assert_eq!(a.actual, -50 + 90);
+        assert!((a.luck - (a.actual as f64 - a.ev)).abs() < 1e-9);
+        let c = &rsp.players[2];
+        assert_eq!(c.hands, 1);
+        assert_eq!(c.actual, -120);
+        assert_eq!(c.ev, -120.0);
+    }
+
+    #[test]
+    fn test_hand_ev_three_way_chop() {
+        // 公共牌是皇家同花顺，三人平分底池
+        let chop = r#"{"hand_id":"3","deal_cards":["As","Ks","Qs","Js","Ts"],"players":[
+            {"uid":"A","hands":["2c","3d"],"invested":100,"won":100},
+            {"uid":"B","hands":["4c","5d"],"invested":100,"won":100},
+            {"uid":"C","hands":["6c","7d"],"invested":100,"won":100}]}"#;
+        let hand: AllInHand = serde_json::from_str(chop).unwrap();
+        let ev = Evaluator {}.hand_ev(&hand).unwrap();
+        assert_eq!(ev.len(), 3);
+        // 净赢之和为0，即分出的份额之和等于底池
+        assert!(ev.iter().sum::<f64>().abs() < 1e-9);
+        for x in ev {
+            assert!(x.abs() < 1e-9);
+        }
+    }
+}
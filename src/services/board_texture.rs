@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+
+use crate::models::card::{Board, Card, CardSet, HandCategory, Rank};
+use crate::models::error_model;
+use crate::models::model::{
+    BoardHand, BoardPairing, BoardTextureReq, BoardTextureRsp, PossibleStraight, Street,
+    SuitTexture,
+};
+use crate::services::evaluator::{Evaluator, CARDS};
+
+// 最多返回的牌力种数
+const MAX_TOP: usize = 20;
+
+// CARDS的掩码每个花色占16位，低13位为点数
+const SUIT_LANES: usize = 4;
+const LANE_BITS: usize = 16;
+const RANK_MASK: u64 = 0x1fff;
+
+impl Evaluator {
+    pub fn board_texture(&self, req: BoardTextureReq) -> BoardTextureRsp {
+        let mut rsp = BoardTextureRsp {
+            code: 0,
+            street: None,
+            pairing: BoardPairing::Unpaired,
+            suits: SuitTexture::Rainbow,
+            flush_possible: false,
+            flush_draw_possible: false,
+            connectedness: 0,
+            straights: vec![],
+            top_hands: vec![],
+            msg: "".to_string(),
+        };
+        let street = match req.deal_cards.len() {
+            3 => Street::Flop,
+            4 => Street::Turn,
+            5 => Street::River,
+            _ => {
+                rsp.code = error_model::ERROR_INVALID;
+                rsp.msg = "deal cards should be between 3 and 5".to_string();
+                return rsp;
+            }
+        };
+        if !(1..=MAX_TOP).contains(&req.top) {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = format!("top should be between 1 and {}", MAX_TOP);
+            return rsp;
+        }
+        rsp.street = Some(street);
+
+        let lanes = suit_lanes(&req.deal_cards);
+        let ranks = lanes.iter().fold(0, |acc, x| acc | x);
+        rsp.pairing = pairing(&lanes);
+        let max_suit = lanes.iter().map(|x| x.count_ones()).max().unwrap();
+        rsp.suits = if max_suit == 1 {
+            SuitTexture::Rainbow
+        } else if max_suit as usize == req.deal_cards.len() {
+            SuitTexture::Monotone
+        } else {
+            SuitTexture::TwoTone
+        };
+        rsp.flush_possible = max_suit >= 3;
+        rsp.flush_draw_possible = street != Street::River && max_suit == 2;
+        let (connectedness, straights) = straights(ranks);
+        rsp.connectedness = connectedness;
+        rsp.straights = straights;
+
+        let board = req.deal_cards.card_set();
+        let notation = req.notation;
+        rsp.top_hands = top_hands(board, req.top)
+            .into_iter()
+            .map(|(value, hands)| BoardHand {
+                category: HandCategory::of(value),
+                value,
+                combos: hands.len() as u32,
+                hands: hands
+                    .iter()
+                    .map(|x| [notation.format(x[0]), notation.format(x[1])])
+                    .collect(),
+            })
+            .collect();
+        return rsp;
+    }
+}
+
+// 用CARDS的掩码按花色拆成4个13位的点数掩码
+fn suit_lanes(board: &Board) -> [u16; SUIT_LANES] {
+    let mask = board
+        .cards()
+        .iter()
+        .fold(0u64, |acc, x| acc | CARDS[x.id()].1);
+    let mut lanes = [0u16; SUIT_LANES];
+    for (i, lane) in lanes.iter_mut().enumerate() {
+        *lane = ((mask >> (i * LANE_BITS)) & RANK_MASK) as u16;
+    }
+    lanes
+}
+
+fn pairing(lanes: &[u16; SUIT_LANES]) -> BoardPairing {
+    let mut counts: Vec<u32> = (0..Rank::ALL.len())
+        .map(|r| lanes.iter().filter(|x| *x & (1 << r) != 0).count() as u32)
+        .filter(|x| *x >= 2)
+        .collect();
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    match counts.as_slice() {
+        [] => BoardPairing::Unpaired,
+        [4, ..] => BoardPairing::Quads,
+        [3, 2, ..] => BoardPairing::FullHouse,
+        [3, ..] => BoardPairing::Trips,
+        [2, 2, ..] => BoardPairing::TwoPair,
+        _ => BoardPairing::Paired,
+    }
+}
+
+// 每个顺子窗口中公共牌已有的点数，最多还差两张时可能成顺
+fn straights(ranks: u16) -> (u32, Vec<PossibleStraight>) {
+    // 第0位为A作1，第i位为点数i-1
+    let extended = (ranks << 1) | (ranks >> 12 & 1);
+    let mut connectedness = 0;
+    let mut straights = vec![];
+    for low in (0..10).rev() {
+        let window = (extended >> low) & 0b11111;
+        connectedness = connectedness.max(window.count_ones());
+        if window.count_ones() < 3 {
+            continue;
+        }
+        let rank = |bit: usize| Rank::ALL[(low + bit + 12) % 13].to_char().to_string();
+        straights.push(PossibleStraight {
+            high: rank(4),
+            needs: (0..5)
+                .rev()
+                .filter(|bit| window & (1 << bit) == 0)
+                .map(rank)
+                .collect(),
+        });
+    }
+    (connectedness, straights)
+}
+
+// 穷举所有手牌组合，按牌力从大到小取前top种
+fn top_hands(board: CardSet, top: usize) -> Vec<(u16, Vec<[Card; 2]>)> {
+    let board_hand = board.hand();
+    let alive: Vec<Card> = board.complement().iter().collect();
+    let mut values: BTreeMap<u16, Vec<[Card; 2]>> = BTreeMap::new();
+    for i in 0..alive.len() {
+        for j in i + 1..alive.len() {
+            let hand = [alive[j], alive[i]];
+            let value = (board_hand + CardSet::from_iter(hand).hand()).evaluate();
+            values.entry(value).or_default().push(hand);
+        }
+    }
+    values.into_iter().rev().take(top).collect()
+}
@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::card::{CardNotation, HandCategory, NotatedCard};
+    use crate::models::error_model;
+    use crate::models::model::{
+        BoardHand, BoardPairing, BoardTextureReq, BoardTextureRsp, Street, SuitTexture,
+    };
+    use crate::services::evaluator::Evaluator;
+
+    fn texture(board: &str, top: usize) -> BoardTextureRsp {
+        let req = BoardTextureReq {
+            deal_cards: board.parse().unwrap(),
+            top,
+            notation: CardNotation::Short,
+        };
+        Evaluator {}.board_texture(req)
+    }
+
+    fn hands(hand: &BoardHand) -> Vec<String> {
+        hand.hands
+            .iter()
+            .map(|x| {
+                x.iter()
+                    .map(|x| match x {
+                        NotatedCard::Text(text) => text.clone(),
+                        NotatedCard::Id(id) => id.to_string(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_flop_texture() {
+        let rsp = texture("8h7h2c", 4);
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.street, Some(Street::Flop));
+        assert_eq!(rsp.pairing, BoardPairing::Unpaired);
+        assert_eq!(rsp.suits, SuitTexture::TwoTone);
+        assert!(!rsp.flush_possible);
+        assert!(rsp.flush_draw_possible);
+        assert_eq!(rsp.connectedness, 2);
+        assert!(rsp.straights.is_empty());
+        // 没有顺子和同花可能时坚果是顶三条
+        let top: Vec<(HandCategory, u32)> = rsp
+            .top_hands
+            .iter()
+            .map(|x| (x.category, x.combos))
+            .collect();
+        assert_eq!(
+            top,
+            vec![
+                (HandCategory::ThreeOfAKind, 3),
+                (HandCategory::ThreeOfAKind, 3),
+                (HandCategory::ThreeOfAKind, 3),
+                (HandCategory::TwoPair, 9)
+            ]
+        );
+        assert_eq!(hands(&rsp.top_hands[0]), vec!["8c8s", "8d8s", "8d8c"]);
+    }
+
+    #[test]
+    fn test_turn_straights_and_flush() {
+        let rsp = texture("9s8s7d6s", 2);
+        assert_eq!(rsp.street, Some(Street::Turn));
+        assert_eq!(rsp.suits, SuitTexture::TwoTone);
+        assert!(rsp.flush_possible);
+        assert!(!rsp.flush_draw_possible);
+        assert_eq!(rsp.connectedness, 4);
+        let straights: Vec<(String, String)> = rsp
+            .straights
+            .iter()
+            .map(|x| (x.high.clone(), x.needs.concat()))
+            .collect();
+        assert_eq!(
+            straights,
+            vec![
+                ("J".to_string(), "JT".to_string()),
+                ("T".to_string(), "T".to_string()),
+                ("9".to_string(), "5".to_string()),
+                ("8".to_string(), "54".to_string())
+            ]
+        );
+        assert_eq!(rsp.top_hands[0].category, HandCategory::StraightFlush);
+        assert_eq!(hands(&rsp.top_hands[0]), vec!["Ts7s"]);
+        assert_eq!(hands(&rsp.top_hands[1]), vec!["7s5s"]);
+    }
+
+    #[test]
+    fn test_river_and_monotone() {
+        let rsp = texture("KsKdKh5c5d", 1);
+        assert_eq!(rsp.street, Some(Street::River));
+        assert_eq!(rsp.pairing, BoardPairing::FullHouse);
+        assert!(!rsp.flush_draw_possible);
+        // Kc加A组成A踢脚的四条
+        assert_eq!(rsp.top_hands[0].category, HandCategory::FourOfAKind);
+        assert_eq!(rsp.top_hands[0].combos, 4);
+
+        let rsp = texture("AhKhQh", 1);
+        assert_eq!(rsp.suits, SuitTexture::Monotone);
+        assert_eq!(rsp.connectedness, 3);
+        assert_eq!(rsp.straights[0].needs.concat(), "JT");
+        assert_eq!(hands(&rsp.top_hands[0]), vec!["JhTh"]);
+
+        assert_eq!(texture("AhKh", 1).code, error_model::ERROR_INVALID);
+        assert_eq!(texture("AhKhQh", 0).code, error_model::ERROR_INVALID);
+    }
+}
@@ -0,0 +1,250 @@
+use holdem_hand_evaluator::Hand;
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::models::card::{Board, Card, MAX_BOARD};
+use crate::models::error_model;
+use crate::models::model::{
+    CalculateOutsReq, CalculateOutsRsp, CalculateRatingReq, CalculateRatingRsp, CardsInfo,
+    ClientRate, DoubleBoardRate, UserCards,
+};
+use crate::services::evaluator::{
+    calculate_rating_valid, deck_valid, draw_cards, for_each_ordered_runout, showdown_winners,
+    Evaluator,
+};
+use crate::utils::log::log_info_display;
+
+// 两组公共牌共剩余3张及以上时的采样次数
+const DOUBLE_BOARD_SAMPLES: u64 = 10000;
+
+// 两组公共牌的结果统计，下标与clients一致
+struct DoubleBoardTally {
+    // 两组胜率之和，平局算一半，与单公共牌的rate含义一致
+    equity: Vec<f64>,
+    first: Vec<f64>,
+    second: Vec<f64>,
+    scoop: Vec<u64>,
+    win_one: Vec<u64>,
+    chop: Vec<u64>,
+    total: u64,
+}
+
+impl DoubleBoardTally {
+    fn new(clients: usize) -> Self {
+        DoubleBoardTally {
+            equity: vec![0.0; clients],
+            first: vec![0.0; clients],
+            second: vec![0.0; clients],
+            scoop: vec![0; clients],
+            win_one: vec![0; clients],
+            chop: vec![0; clients],
+            total: 0,
+        }
+    }
+
+    fn add(&mut self, user_cards: &[CardsInfo], first: Hand, second: Hand) {
+        let first = showdown_winners(user_cards, first);
+        let second = showdown_winners(user_cards, second);
+        for i in 0..user_cards.len() {
+            let won_first = first.contains(&i);
+            let won_second = second.contains(&i);
+            if won_first {
+                self.first[i] += 1.0 / first.len() as f64;
+            }
+            if won_second {
+                self.second[i] += 1.0 / second.len() as f64;
+            }
+            let alone_first = won_first && first.len() == 1;
+            let alone_second = won_second && second.len() == 1;
+            for (won, alone) in [(won_first, alone_first), (won_second, alone_second)] {
+                if alone {
+                    self.equity[i] += 1.0;
+                } else if won {
+                    self.equity[i] += 0.5;
+                }
+            }
+            if alone_first && alone_second {
+                self.scoop[i] += 1;
+            } else if alone_first || alone_second {
+                self.win_one[i] += 1;
+            } else if won_first || won_second {
+                self.chop[i] += 1;
+            }
+        }
+        self.total += 1;
+    }
+
+    fn rate(&self, x: f64) -> u64 {
+        (x * 10000.0 / self.total as f64).round() as u64
+    }
+
+    fn clients_rate(&self, user_cards: &[CardsInfo]) -> (Vec<ClientRate>, Vec<DoubleBoardRate>) {
+        let mut clients_rate = vec![];
+        let mut double_board = vec![];
+        for (i, user_card) in user_cards.iter().enumerate() {
+            // 每组公共牌各分一半底池
+            clients_rate.push(ClientRate {
+                uid: user_card.uid.clone(),
+                rate: self.rate(self.equity[i] / 2.0),
+                share: Some(self.rate((self.first[i] + self.second[i]) / 2.0)),
+                ci_low: None,
+                ci_high: None,
+            });
+            double_board.push(DoubleBoardRate {
+                uid: user_card.uid.clone(),
+                first: self.rate(self.first[i]),
+                second: self.rate(self.second[i]),
+                scoop: self.rate(self.scoop[i] as f64),
+                win_one: self.rate(self.win_one[i] as f64),
+                chop: self.rate(self.chop[i] as f64),
+            });
+        }
+        (clients_rate, double_board)
+    }
+}
+
+// 第二组公共牌的张数与第一组相同，且不能与手牌、第一组公共牌和dead_cards重复
+fn second_board_valid(
+    clients: &[UserCards],
+    deal_cards: &Board,
+    dead_cards: &[Card],
+    second_board: &Board,
+    streets: &[usize],
+) -> Result<(), String> {
+    if second_board.len() != deal_cards.len() || !streets.contains(&deal_cards.len()) {
+        return Err(format!(
+            "both boards should have the same number of cards in {:?}",
+            streets
+        ));
+    }
+    let mut known = deal_cards.card_set();
+    known.extend(dead_cards.iter().copied());
+    clients.iter().for_each(|x| known.extend(x.hands));
+    if second_board.cards().iter().any(|x| !known.insert(*x)) {
+        return Err("second board has duplicates".to_string());
+    }
+    Ok(())
+}
+
+impl Evaluator {
+    // 双公共牌底池：两组公共牌共用一副牌，各自比牌分一半底池
+    pub(crate) fn double_board_rating(&self, req: CalculateRatingReq) -> CalculateRatingRsp {
+        let mut rsp = CalculateRatingRsp {
+            code: 0,
+            clients_rate: vec![],
+            samples: 0,
+            seed: 0,
+            completed: false,
+            exact: false,
+            double_board: None,
+            msg: "".to_string(),
+        };
+        let second_board = req.second_board.clone().unwrap_or_default();
+        let (valid, user_cards) = calculate_rating_valid(&req);
+        // 双公共牌按剩余张数穷举或采样10000次，不支持exact、按精度或时间停止和指定采样次数
+        let checked = if req.exact
+            || req.target_std_err.is_some()
+            || req.time_budget_ms.is_some()
+            || req.samples.is_some()
+        {
+            Err("second_board only supports the default sampling".to_string())
+        } else if valid {
+            second_board_valid(
+                &req.clients,
+                &req.deal_cards,
+                &req.dead_cards,
+                &second_board,
+                &[0, 3, 4, 5],
+            )
+        } else {
+            Err("req has duplicates or has empty string input,or client.len is lt 2".to_string())
+        };
+        if let Err(msg) = checked {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = msg;
+            return rsp;
+        }
+        let hands = req.clients.iter().flat_map(|x| x.hands);
+        let mut deck = self.get_alive_cards(
+            &req.deal_cards,
+            &req.dead_cards,
+            hands.chain(second_board.cards().iter().copied()),
+        );
+        let remain_card = MAX_BOARD - req.deal_cards.len();
+        // 两组公共牌各自发完剩余的牌
+        if let Err(msg) = deck_valid(deck.len(), 2 * remain_card) {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = msg;
+            return rsp;
+        }
+        let first = req.deal_cards.hand();
+        let second = second_board.hand();
+        let runout =
+            |board: Hand, cards: &[Card]| cards.iter().fold(board, |acc, x| acc.add_card(x.id()));
+        let mut tally = DoubleBoardTally::new(user_cards.len());
+        let seed = req.seed.unwrap_or_else(|| thread_rng().gen());
+        log_info_display("double board rating seed", &seed);
+        if remain_card <= 1 {
+            // 两组共剩余不超过2张时穷举，两组的牌有先后之分
+            for_each_ordered_runout(&deck, 2 * remain_card, &mut |cards| {
+                let first = runout(first, &cards[..remain_card]);
+                let second = runout(second, &cards[remain_card..]);
+                tally.add(&user_cards, first, second);
+            });
+        } else {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            for _ in 0..DOUBLE_BOARD_SAMPLES {
+                // 前remain_card张给第一组，之后的给第二组
+                let cards = draw_cards(&mut rng, &mut deck, 2 * remain_card);
+                let first = runout(first, &cards[..remain_card]);
+                let second = runout(second, &cards[remain_card..]);
+                tally.add(&user_cards, first, second);
+            }
+        }
+        let (clients_rate, double_board) = tally.clients_rate(&user_cards);
+        rsp.clients_rate = clients_rate;
+        rsp.double_board = Some(double_board);
+        rsp.samples = tally.total;
+        rsp.seed = seed;
+        rsp.completed = true;
+        rsp.exact = remain_card <= 1;
+        return rsp;
+    }
+
+    // 每组公共牌分别计算下一张牌的outs，另一组公共牌视为已发出的牌
+    pub(crate) fn double_board_outs(&self, req: CalculateOutsReq) -> CalculateOutsRsp {
+        let second_board = req.second_board.clone().unwrap_or_default();
+        if let Err(msg) = second_board_valid(
+            &req.clients,
+            &req.deal_cards,
+            &req.dead_cards,
+            &second_board,
+            &[3, 4],
+        ) {
+            return CalculateOutsRsp {
+                code: error_model::ERROR_INVALID,
+                outs: vec![],
+                second_outs: None,
+                msg,
+            };
+        }
+        let board_outs = |board: &Board, other: &Board| {
+            let mut dead_cards = req.dead_cards.clone();
+            dead_cards.extend(other.cards());
+            self.outs(CalculateOutsReq {
+                clients: req.clients.clone(),
+                deal_cards: board.clone(),
+                dead_cards,
+                notation: req.notation,
+                second_board: None,
+            })
+        };
+        let mut rsp = board_outs(&req.deal_cards, &second_board);
+        if rsp.code != 0 {
+            return rsp;
+        }
+        let second = board_outs(&second_board, &req.deal_cards);
+        rsp.second_outs = Some(second.outs);
+        return rsp;
+    }
+}
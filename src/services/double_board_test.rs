@@ -0,0 +1,183 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::card::{parse_cards, CardNotation};
+    use crate::models::error_model;
+    use crate::models::model::{
+        CalculateOutsReq, CalculateRatingReq, CalculateRatingRsp, Outs, UserCards,
+    };
+    use crate::services::evaluator::Evaluator;
+
+    fn clients() -> Vec<UserCards> {
+        ["AsAh", "KsKh"]
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                let cards = parse_cards(x).unwrap();
+                UserCards {
+                    hands: [cards[0], cards[1]],
+                    uid: i.to_string(),
+                }
+            })
+            .collect()
+    }
+
+    fn rating_req(first: &str, second: &str) -> CalculateRatingReq {
+        CalculateRatingReq {
+            clients: clients(),
+            deal_cards: first.parse().unwrap(),
+            dead_cards: vec![],
+            exact: false,
+            target_std_err: None,
+            time_budget_ms: None,
+            seed: Some(5),
+            samples: None,
+            second_board: Some(second.parse().unwrap()),
+        }
+    }
+
+    fn rating(first: &str, second: &str) -> CalculateRatingRsp {
+        Evaluator {}.rating(rating_req(first, second))
+    }
+
+    fn rates(rsp: &CalculateRatingRsp) -> Vec<(u64, u64, u64, u64, u64, u64)> {
+        rsp.clients_rate
+            .iter()
+            .zip(rsp.double_board.as_ref().unwrap())
+            .map(|(x, y)| (x.rate, y.first, y.second, y.scoop, y.win_one, y.chop))
+            .collect()
+    }
+
+    #[test]
+    fn test_river_boards() {
+        // 第一组AA赢，第二组KK成四条
+        let rsp = rating("2c7d9hJdQc", "KcKd3s4h8c");
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.samples, 1);
+        assert_eq!(
+            rates(&rsp),
+            vec![(5000, 10000, 0, 0, 10000, 0), (5000, 0, 10000, 0, 10000, 0)]
+        );
+
+        // 两组都是AA赢
+        let rsp = rating("2c7d9hJdQc", "2d7c9sJcQd");
+        assert_eq!(rates(&rsp)[0], (10000, 10000, 10000, 10000, 0, 0));
+
+        // 第二组公共牌成顺子平分
+        let rsp = rating("2c7d9hJdQc", "TcJsQsKdAd");
+        assert_eq!(
+            rates(&rsp),
+            vec![
+                (7500, 10000, 5000, 0, 10000, 0),
+                (2500, 0, 5000, 0, 0, 10000)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_three_way_chop() {
+        // 第一组皇家同花顺三人平分，第二组只有第三个玩家成葫芦
+        let mut req = rating_req("AhKhQhJhTh", "7s7h9d9hKs");
+        req.clients = ["2c3d", "4c5d", "6c7d"]
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                let cards = parse_cards(x).unwrap();
+                UserCards {
+                    hands: [cards[0], cards[1]],
+                    uid: i.to_string(),
+                }
+            })
+            .collect();
+        let rsp = Evaluator {}.rating(req);
+        assert_eq!(rsp.code, 0);
+        // rate平局算一半，share按三人均分底池
+        let rates: Vec<(u64, Option<u64>)> =
+            rsp.clients_rate.iter().map(|x| (x.rate, x.share)).collect();
+        assert_eq!(
+            rates,
+            vec![(2500, Some(1667)), (2500, Some(1667)), (7500, Some(6667))]
+        );
+    }
+
+    #[test]
+    fn test_enumerate_and_sample() {
+        // 两组转牌各剩1张，穷举40张中有先后的两张
+        let rsp = rating("2c7d9hJd", "KcKd3s4h");
+        assert_eq!(rsp.samples, 40 * 39);
+        let total: u64 = rsp.clients_rate.iter().map(|x| x.rate).sum();
+        assert!((9999..=10001).contains(&total));
+
+        let rsp = rating("2c7d9h", "KcKd3s");
+        assert_eq!(rsp.samples, 10000);
+        assert_eq!(rsp.seed, 5);
+        assert_eq!(rates(&rsp), rates(&rating("2c7d9h", "KcKd3s")));
+    }
+
+    #[test]
+    fn test_double_board_invalid() {
+        assert_eq!(
+            rating("2c7d9h", "KcKd3s4h").code,
+            error_model::ERROR_INVALID
+        );
+        assert_eq!(rating("2c7d9h", "2cKd3s").code, error_model::ERROR_INVALID);
+        assert_eq!(rating("2c7d9h", "AsKd3s").code, error_model::ERROR_INVALID);
+
+        // 与dead_cards重复
+        let mut req = rating_req("2c7d9h", "KcKd3s");
+        req.dead_cards = parse_cards("3s").unwrap();
+        assert_eq!(Evaluator {}.rating(req).code, error_model::ERROR_INVALID);
+        let req = CalculateOutsReq {
+            clients: clients(),
+            deal_cards: "2c7d9hJd".parse().unwrap(),
+            dead_cards: parse_cards("4h").unwrap(),
+            notation: CardNotation::Short,
+            second_board: Some("KcKd3s4h".parse().unwrap()),
+        };
+        assert_eq!(Evaluator {}.outs(req).code, error_model::ERROR_INVALID);
+
+        // 不支持按精度、时间或固定次数采样
+        let mut req = rating_req("2c7d9h", "KcKd3s");
+        req.exact = true;
+        assert_eq!(Evaluator {}.rating(req).code, error_model::ERROR_INVALID);
+        let mut req = rating_req("2c7d9h", "KcKd3s");
+        req.target_std_err = Some(100);
+        assert_eq!(Evaluator {}.rating(req).code, error_model::ERROR_INVALID);
+        let mut req = rating_req("2c7d9h", "KcKd3s");
+        req.time_budget_ms = Some(100);
+        assert_eq!(Evaluator {}.rating(req).code, error_model::ERROR_INVALID);
+        let mut req = rating_req("2c7d9h", "KcKd3s");
+        req.samples = Some(100);
+        assert_eq!(Evaluator {}.rating(req).code, error_model::ERROR_INVALID);
+
+        // 剩余的牌不够两组公共牌各发完
+        for (first, second, alive) in [("2c7d9h", "KcKd3s", 3), ("2c7d9hJd", "KcKd3s4h", 1)] {
+            let mut req = rating_req(first, second);
+            let mut known = req.deal_cards.card_set();
+            known.extend(req.second_board.as_ref().unwrap().cards().iter().copied());
+            known.extend(req.clients.iter().flat_map(|x| x.hands));
+            req.dead_cards = known.complement().iter().skip(alive).collect();
+            assert_eq!(Evaluator {}.rating(req).code, error_model::ERROR_INVALID);
+        }
+    }
+
+    #[test]
+    fn test_double_board_outs() {
+        let req = CalculateOutsReq {
+            clients: clients(),
+            deal_cards: "2c7d9hJd".parse().unwrap(),
+            dead_cards: vec![],
+            notation: CardNotation::Short,
+            second_board: Some("KcKd3s4h".parse().unwrap()),
+        };
+        let rsp = Evaluator {}.outs(req);
+        assert_eq!(rsp.code, 0);
+        let count =
+            |outs: &[Outs], uid: &str| outs.iter().find(|x| x.uid == uid).unwrap().cards.len();
+        // 第一组KK没有outs，第二组KK已成四条
+        assert_eq!(count(&rsp.outs, "0"), 40);
+        assert_eq!(count(&rsp.outs, "1"), 0);
+        let second = rsp.second_outs.unwrap();
+        assert_eq!(count(&second, "0"), 0);
+        assert_eq!(count(&second, "1"), 40);
+    }
+}
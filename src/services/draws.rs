@@ -0,0 +1,195 @@
+use crate::models::card::{Card, CardNotation, CardSet, HandCategory, Rank, Suit};
+use crate::models::error_model;
+use crate::models::model::{ClientDraws, Draw, DrawKind, DrawsReq, DrawsRsp, UserCards};
+use crate::services::evaluator::Evaluator;
+
+impl Evaluator {
+    // 只看自己的手牌和公共牌，outs去掉所有已知的牌，不考虑对手是否更大
+    pub fn draws(&self, req: DrawsReq) -> DrawsRsp {
+        let mut rsp = DrawsRsp {
+            code: 0,
+            clients: vec![],
+            msg: "".to_string(),
+        };
+        let mut known = req.deal_cards.card_set();
+        known.extend(req.dead_cards.iter().copied());
+        let duplicated = req
+            .clients
+            .iter()
+            .flat_map(|x| x.hands)
+            .any(|x| !known.insert(x));
+        if duplicated || req.clients.is_empty() || !(3..=4).contains(&req.deal_cards.len()) {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg =
+                "req has duplicates or has no clients, or deal cards should be 3 or 4".to_string();
+            return rsp;
+        }
+        let (_, alive_cards) =
+            self.get_board_and_alive_cards(&req.deal_cards, &req.dead_cards, &req.clients);
+        let alive: CardSet = alive_cards.into_iter().collect();
+        let board = req.deal_cards.card_set();
+        for client in &req.clients {
+            rsp.clients
+                .push(client_draws(client, board, alive, req.notation));
+        }
+        return rsp;
+    }
+}
+
+fn client_draws(
+    client: &UserCards,
+    board: CardSet,
+    alive: CardSet,
+    notation: CardNotation,
+) -> ClientDraws {
+    let hole: CardSet = client.hands.into_iter().collect();
+    let all = board.union(hole);
+    let flop = board.len() == 3;
+    let mut draws: Vec<(DrawKind, CardSet)> = vec![];
+    let category = HandCategory::of(all.hand().evaluate());
+
+    // 同花听牌: 4张同花且至少一张是手牌
+    for suit in Suit::ALL {
+        let count = suit_count(all, suit);
+        if suit_count(hole, suit) == 0 || category >= HandCategory::Flush {
+            continue;
+        }
+        let outs = cards_of(alive, |x| x.suit() == suit);
+        if count == 4 {
+            // 公共牌之外最大的该花色在自己手里
+            let nut = Rank::ALL
+                .iter()
+                .rev()
+                .map(|x| Card::new(*x, suit))
+                .find(|x| !board.contains(*x))
+                .is_some_and(|x| hole.contains(x));
+            let kind = if nut {
+                DrawKind::NutFlushDraw
+            } else {
+                DrawKind::FlushDraw
+            };
+            draws.push((kind, outs));
+        } else if count == 3 && flop {
+            draws.push((DrawKind::BackdoorFlush, outs));
+        }
+    }
+
+    // 顺子听牌: 补一张牌成顺且只靠公共牌不成顺
+    let all_ranks = rank_mask(all);
+    let board_ranks = rank_mask(board);
+    let completes =
+        |ranks: u16| has_straight(all_ranks | ranks) && !has_straight(board_ranks | ranks);
+    let mut straight_draw = false;
+    if !has_straight(all_ranks) {
+        let ranks: Vec<usize> = (0..13).filter(|&r| completes(1 << r)).collect();
+        let outs = cards_of(alive, |x| ranks.contains(&x.rank().index()));
+        let kind = match ranks.len() {
+            0 => None,
+            1 => Some(DrawKind::Gutshot),
+            _ if open_ended(all_ranks, &ranks) => Some(DrawKind::OpenEnded),
+            _ => Some(DrawKind::DoubleGutshot),
+        };
+        if let Some(kind) = kind {
+            draws.push((kind, outs));
+            straight_draw = true;
+        } else if flop {
+            // 后门顺子: 转牌和河牌各补一张成顺
+            let mut backdoor = 0u16;
+            for a in 0..13 {
+                for b in a + 1..13 {
+                    let ranks = (1 << a) | (1 << b);
+                    if all_ranks & ranks == 0 && completes(ranks) {
+                        backdoor |= ranks;
+                    }
+                }
+            }
+            if backdoor != 0 {
+                let outs = cards_of(alive, |x| backdoor & (1 << x.rank().index()) != 0);
+                draws.push((DrawKind::BackdoorStraight, outs));
+            }
+        }
+    }
+
+    // 高张: 没有成对时比公共牌都大的手牌，outs是配对的牌
+    let board_high = board.iter().map(|x| x.rank()).max().unwrap();
+    let overcards: Vec<Rank> = client
+        .hands
+        .iter()
+        .map(|x| x.rank())
+        .filter(|x| *x > board_high)
+        .collect();
+    if category == HandCategory::HighCard && !overcards.is_empty() {
+        draws.push((
+            DrawKind::Overcards,
+            cards_of(alive, |x| overcards.contains(&x.rank())),
+        ));
+    }
+
+    let flush_draw = draws
+        .iter()
+        .find(|x| matches!(x.0, DrawKind::FlushDraw | DrawKind::NutFlushDraw));
+    if let (Some(flush), true) = (flush_draw, straight_draw) {
+        let straight = draws
+            .iter()
+            .find(|x| {
+                matches!(
+                    x.0,
+                    DrawKind::OpenEnded | DrawKind::Gutshot | DrawKind::DoubleGutshot
+                )
+            })
+            .unwrap();
+        let outs = flush.1.union(straight.1);
+        draws.push((DrawKind::ComboDraw, outs));
+    }
+
+    // 后门听牌的outs只是成为听牌，不计入总的outs
+    let outs: CardSet = draws
+        .iter()
+        .filter(|x| !matches!(x.0, DrawKind::BackdoorFlush | DrawKind::BackdoorStraight))
+        .fold(CardSet::new(), |acc, x| acc.union(x.1));
+    let format = |set: CardSet| set.iter().map(|x| notation.format(x)).collect();
+    return ClientDraws {
+        uid: client.uid.clone(),
+        draws: draws
+            .into_iter()
+            .map(|(kind, outs)| Draw {
+                kind,
+                outs: format(outs),
+            })
+            .collect(),
+        outs: format(outs),
+    };
+}
+
+fn suit_count(cards: CardSet, suit: Suit) -> usize {
+    cards.iter().filter(|x| x.suit() == suit).count()
+}
+
+fn cards_of(alive: CardSet, f: impl Fn(&Card) -> bool) -> CardSet {
+    alive.iter().filter(|x| f(x)).collect()
+}
+
+// 第i位为点数i，0为2，12为A
+fn rank_mask(cards: CardSet) -> u16 {
+    cards.iter().fold(0, |acc, x| acc | (1 << x.rank().index()))
+}
+
+// A同时作为1，最低的顺子是A2345
+fn has_straight(ranks: u16) -> bool {
+    let ranks = (ranks << 1) | (ranks >> 12 & 1);
+    (0..10).any(|low| (ranks >> low) & 0b11111 == 0b11111)
+}
+
+// 连续4张且两端都能成顺，如5678听4和9
+fn open_ended(ranks: u16, completes: &[usize]) -> bool {
+    let extended = (ranks << 1) | (ranks >> 12 & 1);
+    // 扩展后第i位为点数i-1，第0位为A作1
+    let position = |r: usize| r + 1;
+    (1..10).any(|low| {
+        (extended >> low) & 0b1111 == 0b1111
+            && completes
+                .iter()
+                .any(|&r| position(r) == low - 1 || (low == 1 && r == 12))
+            && completes.iter().any(|&r| position(r) == low + 4)
+    })
+}
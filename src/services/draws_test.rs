@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::card::{parse_cards, CardNotation, NotatedCard};
+    use crate::models::model::{ClientDraws, DrawKind, DrawsReq, UserCards};
+    use crate::services::evaluator::Evaluator;
+
+    fn draws(hands: &[&str], board: &str) -> Vec<ClientDraws> {
+        let clients = hands
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let cards = parse_cards(s).unwrap();
+                UserCards {
+                    hands: [cards[0], cards[1]],
+                    uid: (i + 1).to_string(),
+                }
+            })
+            .collect();
+        let req = DrawsReq {
+            clients,
+            deal_cards: board.parse().unwrap(),
+            dead_cards: vec![],
+            notation: CardNotation::Short,
+        };
+        let rsp = Evaluator {}.draws(req);
+        assert_eq!(rsp.code, 0);
+        rsp.clients
+    }
+
+    fn kinds(client: &ClientDraws) -> Vec<DrawKind> {
+        client.draws.iter().map(|x| x.kind).collect()
+    }
+
+    fn outs(client: &ClientDraws, kind: DrawKind) -> String {
+        let draw = client.draws.iter().find(|x| x.kind == kind).unwrap();
+        draw.outs
+            .iter()
+            .map(|x| match x {
+                NotatedCard::Text(text) => text.clone(),
+                NotatedCard::Id(id) => id.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_flush_and_straight_draws() {
+        let clients = draws(&["AhJh", "Th9c", "QsKd"], "8h7h2c");
+        // 坚果同花听牌加两张高张，Th在玩家2手里
+        assert_eq!(
+            kinds(&clients[0]),
+            vec![
+                DrawKind::NutFlushDraw,
+                DrawKind::BackdoorStraight,
+                DrawKind::Overcards
+            ]
+        );
+        assert_eq!(clients[0].outs.len(), 8 + 6);
+        // T9对87是两头顺子听牌，还有后门同花和两张高张
+        assert_eq!(
+            kinds(&clients[1]),
+            vec![
+                DrawKind::BackdoorFlush,
+                DrawKind::OpenEnded,
+                DrawKind::Overcards
+            ]
+        );
+        assert_eq!(outs(&clients[1], DrawKind::OpenEnded), "6s6h6c6dJsJcJd");
+        assert_eq!(clients[1].outs.len(), 7 + 6);
+        // 两张高张，KQ对87差3张，没有后门顺子
+        assert_eq!(kinds(&clients[2]), vec![DrawKind::Overcards]);
+        assert_eq!(outs(&clients[2], DrawKind::Overcards), "QhQcQdKsKhKc");
+    }
+
+    #[test]
+    fn test_gutshot_and_combo_draw() {
+        let clients = draws(&["9h6h", "AsKs", "Td6c"], "7h8s2h4d");
+        // 同花听牌加两头顺子，转牌没有后门听牌，Td在玩家3手里，5h和Th同时是同花outs
+        assert_eq!(
+            kinds(&clients[0]),
+            vec![
+                DrawKind::FlushDraw,
+                DrawKind::OpenEnded,
+                DrawKind::Overcards,
+                DrawKind::ComboDraw
+            ]
+        );
+        assert_eq!(outs(&clients[0], DrawKind::ComboDraw).len(), (9 + 5) * 2);
+        assert_eq!(clients[0].outs.len(), 9 + 5 + 3);
+        // A2345差3和5两张，不算听牌
+        assert_eq!(kinds(&clients[1]), vec![DrawKind::Overcards]);
+        // T6对8742: 听9和5的双卡顺，9h在玩家1手里
+        assert_eq!(
+            kinds(&clients[2]),
+            vec![DrawKind::DoubleGutshot, DrawKind::Overcards]
+        );
+        assert_eq!(outs(&clients[2], DrawKind::DoubleGutshot).len(), 7 * 2);
+    }
+}
@@ -1,48 +1,65 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::string::ToString;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use holdem_hand_evaluator::Hand;
-use itertools::Itertools;
-use rand::{thread_rng, Rng};
+use rand::{thread_rng, Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
+use crate::models::card::{Board, Card, CardSet};
 use crate::models::error_model;
 use crate::models::model::{
-    CalculateOutsReq, CalculateOutsRsp, CalculateRatingReq, CalculateRatingRsp, CardsInfo,
-    ClientRate, Outs,
+    CalculateOutsReq, CalculateOutsRsp, CalculateRatingReq, CalculateRatingRsp,
+    CalculateTimelineReq, CalculateTimelineRsp, CardsInfo, ClientRate, Outs, RatingProgress,
+    StreetRate, UserCards,
 };
-use crate::utils::log::log_info_debug;
+use crate::utils::log::{log_info_debug, log_info_display};
 
 #[async_trait]
 pub trait CalculateRating {
     async fn calculate_rating(&self, req: CalculateRatingReq) -> CalculateRatingRsp;
     async fn calculate_outs(&self, req: CalculateOutsReq) -> CalculateOutsRsp;
+    async fn calculate_timeline(&self, req: CalculateTimelineReq) -> CalculateTimelineRsp;
 }
 
+// 各街对应的公共牌数量
+const STREETS: [(&str, usize); 4] = [("preflop", 0), ("flop", 3), ("turn", 4), ("river", 5)];
+
 pub struct Evaluator {}
 
-pub fn calculate_rating_valid(req: &CalculateRatingReq) -> (bool, Vec<CardsInfo>) {
-    let length = req.deal_cards.len() + req.clients.len() * 2;
-    let demo = String::from("");
-    let mut vec: Vec<&String> = vec![&demo; length];
-    let mut i = 0;
-    req.clients.iter().for_each(|x1| {
-        vec[i] = &x1.hands[0];
-        vec[i + 1] = &x1.hands[1];
-        i += 2;
-    });
-    req.deal_cards.iter().for_each(|x2| {
-        vec[i] = &x2;
-        i += 1;
-    });
-    if vec.iter().duplicates().count() > 0 {
-        return (false, vec![]);
+// 5到7张牌中组成最大牌力的全部5张组合，牌力相同的组合可能有多个(如公共牌成牌时踢脚不同)
+pub fn best_five(cards: &[Card]) -> (u16, Vec<[Card; 5]>) {
+    let set: CardSet = cards.iter().copied().collect();
+    let value = set.hand().evaluate();
+    let mut best = vec![];
+    for mask in 0u32..1 << cards.len() {
+        if mask.count_ones() != 5 {
+            continue;
+        }
+        let five: Vec<Card> = (0..cards.len())
+            .filter(|i| mask & (1 << i) != 0)
+            .map(|i| cards[i])
+            .collect();
+        let five_set: CardSet = five.iter().copied().collect();
+        if five_set.hand().evaluate() == value {
+            best.push([five[0], five[1], five[2], five[3], five[4]]);
+        }
     }
-    let empty = "".to_string();
-    if vec.into_iter().contains(&empty) {
-        return (false, vec![]);
+    (value, best)
+}
+
+pub fn calculate_rating_valid(req: &CalculateRatingReq) -> (bool, Vec<CardsInfo<'_>>) {
+    // 牌面本身在反序列化时已校验，这里只检查手牌和公共牌之间是否重复
+    let mut known = req.deal_cards.card_set();
+    for client in &req.clients {
+        for card in client.hands {
+            if !known.insert(card) {
+                return (false, vec![]);
+            }
+        }
     }
-    let user_cards = convert(&req);
+    let user_cards = convert(req);
     if user_cards.len() < 2 {
         return (false, vec![]);
     }
@@ -50,59 +67,64 @@ pub fn calculate_rating_valid(req: &CalculateRatingReq) -> (bool, Vec<CardsInfo>
 }
 
 impl Evaluator {
-    fn get_board_and_alive_cards(
+    pub(crate) fn get_board_and_alive_cards(
         &self,
-        deal_cards: &Vec<String>,
-        dead_cards: &Vec<String>,
-        user_cards: &Vec<CardsInfo>,
-    ) -> (Hand, Vec<usize>) {
-        let board = if let Some(board) = deal_cards
-            .iter()
-            .map(|x| x.parse::<Hand>().unwrap())
-            .reduce(|acc, e| acc + e)
-        {
-            board
-        } else {
-            Hand::new()
-        };
-        // 获取全部的hands和board的mark
-        let mut mask = if let Some(mask) =
-            user_cards
-                .iter()
-                .map(|x| x.hands.get_mask())
-                .reduce(|acc, hand| {
-                    return acc | hand;
-                }) {
-            mask
-        } else {
-            0
-        };
-        let dead_cards_hands = if let Some(dead_card) = dead_cards
-            .iter()
-            .map(|x| x.parse::<Hand>().unwrap())
-            .reduce(|acc, e| acc + e)
-        {
-            dead_card
-        } else {
-            Hand::new()
-        };
-        mask = mask | board.get_mask() | dead_cards_hands.get_mask();
-        // // 计算剩余的cards
-        let alive_cards = compute_alive_cards(mask);
-        (board, alive_cards)
+        deal_cards: &Board,
+        dead_cards: &[Card],
+        clients: &[UserCards],
+    ) -> (Hand, Vec<Card>) {
+        let hands = clients.iter().flat_map(|x| x.hands);
+        let alive_cards = self.get_alive_cards(deal_cards, dead_cards, hands);
+        (deal_cards.hand(), alive_cards)
+    }
+
+    // 手牌张数不固定的玩法(如七张梅花)也用这里计算剩余的牌
+    pub(crate) fn get_alive_cards(
+        &self,
+        deal_cards: &Board,
+        dead_cards: &[Card],
+        hands: impl IntoIterator<Item = Card>,
+    ) -> Vec<Card> {
+        // 获取全部的hands、board和dead cards
+        let mut known = deal_cards.card_set();
+        known.extend(hands);
+        known.extend(dead_cards.iter().copied());
+        // 计算剩余的cards
+        known.complement().iter().collect()
     }
 }
 
 #[async_trait]
-// win的牌放在前面，draw的牌放在后面
 impl CalculateRating for Evaluator {
+    async fn calculate_rating(&self, req: CalculateRatingReq) -> CalculateRatingRsp {
+        return self.rating(req);
+    }
     async fn calculate_outs(&self, req: CalculateOutsReq) -> CalculateOutsRsp {
+        return self.outs(req);
+    }
+    async fn calculate_timeline(&self, req: CalculateTimelineReq) -> CalculateTimelineRsp {
+        return self.timeline(req);
+    }
+}
+
+// 同步接口，供游戏服务、离线分析等在进程内直接调用
+impl Evaluator {
+    pub fn rating(&self, req: CalculateRatingReq) -> CalculateRatingRsp {
+        return self.calculate_rating_progress(req, RATING_BUDGET, &mut |_| true);
+    }
+
+    // win的牌放在前面，draw的牌放在后面
+    pub fn outs(&self, req: CalculateOutsReq) -> CalculateOutsRsp {
+        if req.second_board.is_some() {
+            return self.double_board_outs(req);
+        }
         let temp = req.into_rating_req();
         let (valid, user_cards) = calculate_rating_valid(&temp);
         if !valid || req.deal_cards.len() < 3 {
             return CalculateOutsRsp {
                 code: error_model::ERROR_INVALID,
                 outs: vec![],
+                second_outs: None,
                 msg: "eq has duplicates or has empty string input,or client.len is lt 2 or req deal cards should gt 2"
                     .to_string(),
             };
@@ -115,11 +137,11 @@ impl CalculateRating for Evaluator {
         }
         if req.deal_cards.len() < 5 {
             let (board, alive_cards) =
-                self.get_board_and_alive_cards(&req.deal_cards, &req.dead_cards, &user_cards);
+                self.get_board_and_alive_cards(&req.deal_cards, &req.dead_cards, &req.clients);
             let mut i = 0;
             while i < alive_cards.len() {
                 let mut new_board = Hand::new();
-                new_board = new_board.add_card(alive_cards[i]);
+                new_board = new_board.add_card(alive_cards[i].id());
                 new_board = new_board + board;
                 let mut max_evaluate: u16 = 0;
                 let mut max_value_uids = vec![];
@@ -153,12 +175,8 @@ impl CalculateRating for Evaluator {
 
         let mut return_outs = vec![];
         for (uid, outs) in outs_by_uid.into_iter() {
-            let mut outs_string = vec![];
-            for card in outs {
-                outs_string.push(CARDSSTRING[card].to_string());
-            }
             let out = Outs {
-                cards: outs_string,
+                cards: outs.into_iter().map(|x| req.notation.format(x)).collect(),
                 uid: uid.to_string(),
             };
             return_outs.push(out);
@@ -166,223 +184,551 @@ impl CalculateRating for Evaluator {
         return CalculateOutsRsp {
             code: 0,
             outs: return_outs,
+            second_outs: None,
             msg: "".to_string(),
         };
     }
-    async fn calculate_rating(&self, req: CalculateRatingReq) -> CalculateRatingRsp {
+
+    pub fn timeline(&self, req: CalculateTimelineReq) -> CalculateTimelineRsp {
+        let mut rsp = CalculateTimelineRsp {
+            code: 0,
+            streets: vec![],
+            msg: "".to_string(),
+        };
+        if req.deal_cards.len() != 5 {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = "req deal cards should be a complete board of 5 cards".to_string();
+            return rsp;
+        }
+        // 复用胜率和outs的计算，逐街计算
+        for (street, deal_len) in STREETS {
+            let street_req = req.to_street_req(deal_len);
+            let deal_cards = street_req.deal_cards.clone();
+            let rating_rsp = self.rating(street_req);
+            if rating_rsp.code != 0 {
+                rsp.code = rating_rsp.code;
+                rsp.msg = rating_rsp.msg;
+                rsp.streets.clear();
+                return rsp;
+            }
+            let mut outs = vec![];
+            if deal_len == 3 || deal_len == 4 {
+                let outs_req = CalculateOutsReq {
+                    clients: req.clients.clone(),
+                    deal_cards: deal_cards.clone(),
+                    dead_cards: req.dead_cards.clone(),
+                    notation: req.notation,
+                    second_board: None,
+                };
+                outs = self.outs(outs_req).outs;
+            }
+            rsp.streets.push(StreetRate {
+                street: street.to_string(),
+                deal_cards,
+                clients_rate: rating_rsp.clients_rate,
+                outs,
+            });
+        }
+        return rsp;
+    }
+
+    // 计算胜率，计算过程中通过progress回调中间结果，超过budget或progress返回false时返回已计算的部分
+    pub fn calculate_rating_progress(
+        &self,
+        req: CalculateRatingReq,
+        budget: Duration,
+        progress: &mut dyn FnMut(RatingProgress) -> bool,
+    ) -> CalculateRatingRsp {
+        if req.second_board.is_some() {
+            return self.double_board_rating(req);
+        }
         let (valid, user_cards) = calculate_rating_valid(&req);
         if !valid {
             return CalculateRatingRsp {
                 code: error_model::ERROR_INVALID,
                 clients_rate: vec![],
+                samples: 0,
+                seed: 0,
+                completed: false,
+                exact: false,
+                double_board: None,
                 msg: "req has duplicates or has empty string input,or client.len is lt 2"
                     .to_string(),
             };
         }
+        if let Err(msg) = samples_valid(&req) {
+            return CalculateRatingRsp {
+                code: error_model::ERROR_INVALID,
+                clients_rate: vec![],
+                samples: 0,
+                seed: 0,
+                completed: false,
+                exact: false,
+                double_board: None,
+                msg,
+            };
+        }
         let (board, alive_cards) =
-            self.get_board_and_alive_cards(&req.deal_cards, &req.dead_cards, &user_cards);
+            self.get_board_and_alive_cards(&req.deal_cards, &req.dead_cards, &req.clients);
         let remain_card = 5 - board.len();
-        let mut alive_card_index: Vec<i32> = Vec::new();
-        (0..remain_card).for_each(|i| {
-            alive_card_index.push(i as i32 - 1);
-        });
-        // 根据cards进行胜率计算
-        let mut index = 0;
-        let mut win_count_by_uid = HashMap::new();
-        let mut draw_count_by_uid = HashMap::new();
-        let mut draw_count: u64 = 0;
-        // 已经出过的公共牌
-        let mut used_cards: HashSet<i32> = HashSet::new();
-        let max_loop: u32 = 11000;
+        if let Err(msg) = deck_valid(alive_cards.len(), remain_card) {
+            return CalculateRatingRsp {
+                code: error_model::ERROR_INVALID,
+                clients_rate: vec![],
+                samples: 0,
+                seed: 0,
+                completed: false,
+                exact: false,
+                double_board: None,
+                msg,
+            };
+        }
+        // 记录种子，便于复现有争议的计算结果
+        let seed = req.seed.unwrap_or_else(|| thread_rng().gen());
+        log_info_display("rating seed", &seed);
+        // 固定采样次数时仍受接口的计算时间上限约束，超时提前停止并标记未算完，不能作为复现结果
+        let budget = req
+            .time_budget_ms
+            .map_or(budget, |ms| Duration::from_millis(ms).min(budget));
+        let mut clock = ProgressClock::new(Some(Instant::now() + budget));
+        let mut report = |tally: &RatingTally| progress(tally.progress(&user_cards));
         // 如果remain_card >= 3 采用随机法直接计算
-        if remain_card >= 3 {
-            let mut rng = thread_rng();
-            let mut loop_time: u32 = 0;
-            while loop_time < max_loop {
-                let mut new_board = Hand::new();
-                new_board += board;
-                let mut i = 0;
-                while i < remain_card {
-                    let random_number = rng.gen_range(0..alive_cards.len());
-                    if new_board.contains(alive_cards[random_number]) {
-                        continue;
+        let tally = if remain_card >= 3 && !req.exact {
+            // 指定了目标精度或时间时，采样直到满足条件，否则固定采样次数
+            let target = SampleTarget {
+                samples: match req.samples {
+                    Some(samples) => samples,
+                    None if req.target_std_err.is_some() || req.time_budget_ms.is_some() => {
+                        MAX_TARGET_SAMPLES
                     }
-                    new_board = new_board.add_card(alive_cards[random_number]);
-                    i += 1;
-                }
-                add_to_win_count(
-                    &user_cards,
-                    &mut draw_count_by_uid,
-                    &mut draw_count,
-                    new_board,
-                    &mut win_count_by_uid,
-                );
-                loop_time += 1;
-            }
-        } else {
-            // 插入select宏
-            tokio::select! {
-                // 1s足够了
-                _ = async{
-                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-                }=>{
+                    None => MAX_SAMPLES,
                 },
-                _ = async{
-                    // 限制循环次数
-                    let mut loop_time:u32 = 0;
-                    let mut can_remove_first = false;
-                    while (index < remain_card ||remain_card == 0)  && loop_time < max_loop{
-                        loop_time+=1;
-                        let mut index_get=(false,false);
-                        if remain_card != 0{
-                            index_get =  get_index(
-                            &mut alive_card_index,
-                            index,
-                            alive_cards.len() as i32,
-                            &mut used_cards,
-                            &can_remove_first,
-                        );
-                        }
-
-                        match index_get{
-                            (true, _) => break,
-                            (false, true) => {
-                                index -= 1; // 跳到上一层
-                                tokio::task::yield_now().await;
-                                continue;
-                            }
-                            (false, false) => {
-                                if  remain_card == 0 || index == remain_card - 1 {
-                                    can_remove_first = true;
-                                    let mut new_board = Hand::new();
-                                    // let mut debg = vec![];
-                                    // 获取待发的牌
-                                    (0..remain_card).for_each(|i| {
-                                        new_board =
-                                            new_board.add_card(alive_cards[alive_card_index[i] as usize]);
-                                        // debg.push(alive_card_index[i] as usize);
-                                    });
-                                    // log_debug_debug("alive_card_index", &debg);
-                                    new_board = new_board + board;
-                                    add_to_win_count(&user_cards,&mut draw_count_by_uid,&mut draw_count,new_board,&mut win_count_by_uid);
-                                    if remain_card == 0{
-                                        loop_time = max_loop+1;
-                                    }
-                                    // 命中最后一层后，最后一层需要index+1继续
-                                    continue;
-                                }
-                            }
-                        }
-                        index += 1;
-                    }
-                }=>{}
-            }
-        }
-        // 根据win_count_by_uid进行rating的计算
-        let mut total_num: u64 = win_count_by_uid.iter().map(|(_, v)| v).sum();
-        total_num += draw_count;
-        let mut calculate_rating_rsp = CalculateRatingRsp {
+                std_err: req.target_std_err.map(|x| x as f64 / 10000.0),
+                seed,
+            };
+            self.sample_boards(
+                &user_cards,
+                board,
+                &alive_cards,
+                &target,
+                &mut clock,
+                &mut report,
+            )
+        } else {
+            self.enumerate_boards(&user_cards, board, &alive_cards, &mut clock, &mut report)
+        };
+        log_info_debug("draw", &tally.draw);
+        log_info_debug("win", &tally.win);
+        return CalculateRatingRsp {
             code: 0,
-            clients_rate: vec![],
+            clients_rate: tally.clients_rate(&user_cards),
+            samples: tally.total,
+            seed,
+            completed: !clock.stopped,
+            exact: !clock.stopped && tally.population > 0,
+            double_board: None,
             msg: "".to_string(),
         };
-        log_info_debug("draw", &draw_count_by_uid);
-        log_info_debug("win", &win_count_by_uid);
-        for client in &req.clients {
-            let uid = &client.uid;
-            let uid_copy = uid.clone();
-            let zero_u64: u64 = 0;
-            let draw_count_value = draw_count_by_uid.get(uid).unwrap_or_else(|| &zero_u64);
-            let win_count_value = win_count_by_uid.get(uid).unwrap_or_else(|| &zero_u64);
-            calculate_rating_rsp.clients_rate.push(ClientRate {
-                uid: uid_copy,
-                rate: (win_count_value * 10000 + draw_count_value * 5000) / total_num,
-            })
+    }
+
+    // 随机抽取剩余的公共牌进行计算
+    fn sample_boards(
+        &self,
+        user_cards: &[CardsInfo],
+        board: Hand,
+        alive_cards: &[Card],
+        target: &SampleTarget,
+        clock: &mut ProgressClock,
+        progress: &mut dyn FnMut(&RatingTally) -> bool,
+    ) -> RatingTally {
+        let remain_card = 5 - board.len();
+        let mut tally = RatingTally::new(user_cards.len(), 0);
+        let mut rng = ChaCha8Rng::seed_from_u64(target.seed);
+        let mut deck = alive_cards.to_vec();
+        let mut loop_time: u64 = 0;
+        while loop_time < target.samples {
+            if loop_time > 0
+                && loop_time.is_multiple_of(CHECK_STEP)
+                && (clock.check(&tally, progress) || target.reached(&tally))
+            {
+                break;
+            }
+            let new_board = sample_board(&mut rng, board, &mut deck, remain_card);
+            add_to_win_count(user_cards, new_board, &mut tally);
+            loop_time += 1;
         }
-        return calculate_rating_rsp;
+        tally
+    }
+
+    // 穷举剩余公共牌的全部组合进行计算
+    fn enumerate_boards(
+        &self,
+        user_cards: &[CardsInfo],
+        board: Hand,
+        alive_cards: &[Card],
+        clock: &mut ProgressClock,
+        progress: &mut dyn FnMut(&RatingTally) -> bool,
+    ) -> RatingTally {
+        let remain_card = 5 - board.len();
+        let table = binomial_table();
+        let population = table[alive_cards.len()][remain_card];
+        let mut tally = RatingTally::new(user_cards.len(), population);
+        // 按与组合总数互质的步长跳跃枚举，中途的结果近似于无放回的均匀采样
+        let stride = coprime_stride(population);
+        let mut picked = vec![0usize; remain_card];
+        for i in 0..population {
+            if i > 0 && i.is_multiple_of(CHECK_STEP) && clock.check(&tally, progress) {
+                break;
+            }
+            let rank = (i as u128 * stride as u128 % population as u128) as u64;
+            unrank_combination(&table, alive_cards.len(), rank, &mut picked);
+            let mut new_board = board;
+            for index in &picked {
+                new_board = new_board.add_card(alive_cards[*index].id());
+            }
+            add_to_win_count(user_cards, new_board, &mut tally);
+        }
+        tally
     }
 }
 
-fn add_to_win_count(
-    user_cards: &Vec<CardsInfo>,
-    draw_count_by_uid: &mut HashMap<String, u64>,
-    draw_count: &mut u64,
-    new_board: Hand,
-    win_count_by_uid: &mut HashMap<String, u64>,
-) {
+// 非流式接口的计算时间上限
+const RATING_BUDGET: Duration = Duration::from_secs(1);
+// 随机法的采样次数
+const MAX_SAMPLES: u64 = 11000;
+// 每计算多少个公共牌组合检查一次时间
+const CHECK_STEP: u64 = 1024;
+// 进度回调的最小间隔
+pub(crate) const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+// 指定目标精度或时间时的采样次数上限
+const MAX_TARGET_SAMPLES: u64 = 10_000_000;
+// 判断是否达到目标精度前至少的采样次数
+const MIN_TARGET_SAMPLES: u64 = 1024;
+// 穷举时最多剩余的公共牌数量
+const MAX_ENUMERATE: usize = 5;
+
+// 随机法的停止条件
+struct SampleTarget {
+    samples: u64,
+    // 目标标准误差，全部client都不超过时停止
+    std_err: Option<f64>,
+    seed: u64,
+}
+
+impl SampleTarget {
+    fn reached(&self, tally: &RatingTally) -> bool {
+        let Some(std_err) = self.std_err else {
+            return false;
+        };
+        if tally.total < MIN_TARGET_SAMPLES {
+            return false;
+        }
+        (0..tally.win.len()).all(|i| tally.std_err(i) <= std_err)
+    }
+}
+
+// 控制计算的时间上限和进度回调的频率
+pub(crate) struct ProgressClock {
+    // 为None时不限时间，只回调进度
+    deadline: Option<Instant>,
+    last_progress: Instant,
+    // 因超时或调用方取消而提前停止
+    pub(crate) stopped: bool,
+}
+
+impl ProgressClock {
+    pub(crate) fn new(deadline: Option<Instant>) -> Self {
+        ProgressClock {
+            deadline,
+            last_progress: Instant::now(),
+            stopped: false,
+        }
+    }
+
+    // 返回true表示已超时或progress返回false(如客户端已断开)，需要停止计算
+    pub(crate) fn check(
+        &mut self,
+        tally: &RatingTally,
+        progress: &mut dyn FnMut(&RatingTally) -> bool,
+    ) -> bool {
+        let now = Instant::now();
+        if self.deadline.is_some_and(|x| now >= x) {
+            self.stopped = true;
+        } else if now.duration_since(self.last_progress) >= PROGRESS_INTERVAL {
+            self.stopped = !progress(tally);
+            self.last_progress = now;
+        }
+        self.stopped
+    }
+}
+
+// 胜率统计，下标与clients一致
+pub struct RatingTally {
+    pub win: Vec<u64>,
+    pub draw: Vec<u64>,
+    // 平局时按平分的人数均分的底池份额之和
+    pub share: Vec<f64>,
+    // 已计算的公共牌组合数
+    pub total: u64,
+    // 穷举时的组合总数，随机法为0
+    pub population: u64,
+}
+
+impl RatingTally {
+    pub(crate) fn new(clients: usize, population: u64) -> Self {
+        RatingTally {
+            win: vec![0; clients],
+            draw: vec![0; clients],
+            share: vec![0.0; clients],
+            total: 0,
+            population,
+        }
+    }
+
+    pub(crate) fn rate(&self, i: usize) -> u64 {
+        if self.total == 0 {
+            return 0;
+        }
+        (self.win[i] * 10000 + self.draw[i] * 5000) / self.total
+    }
+
+    // 胜率的标准误差，0到1之间
+    fn std_err(&self, i: usize) -> f64 {
+        let n = self.total as f64;
+        let win = self.win[i] as f64;
+        let draw = self.draw[i] as f64;
+        // 每次的得分为1、0.5或0
+        let mean = (win + draw * 0.5) / n;
+        let square = (win + draw * 0.25) / n;
+        let mut variance = (square - mean * mean).max(0.0) / n;
+        // 穷举时为无放回采样，做有限总体修正
+        if self.population > 1 {
+            let population = self.population as f64;
+            variance *= (population - n) / (population - 1.0);
+        }
+        variance.max(0.0).sqrt()
+    }
+
+    fn share(&self, i: usize) -> u64 {
+        if self.total == 0 {
+            return 0;
+        }
+        (self.share[i] * 10000.0 / self.total as f64).round() as u64
+    }
+
+    // 95%置信区间，单位与rate一致
+    pub(crate) fn confidence_interval(&self, i: usize) -> (u64, u64) {
+        if self.total == 0 {
+            return (0, 10000);
+        }
+        let n = self.total as f64;
+        let mean = (self.win[i] as f64 + self.draw[i] as f64 * 0.5) / n;
+        let half = 1.96 * self.std_err(i);
+        // 与rate一样取整到万分之一，下限向下、上限向上，保证包含rate
+        let low = ((mean - half).max(0.0) * 10000.0).floor() as u64;
+        let high = ((mean + half).min(1.0) * 10000.0).ceil() as u64;
+        (low.min(self.rate(i)), high.max(self.rate(i)))
+    }
+
+    pub fn clients_rate(&self, user_cards: &[CardsInfo]) -> Vec<ClientRate> {
+        let mut clients_rate = vec![];
+        for (i, user_card) in user_cards.iter().enumerate() {
+            let (low, high) = self.confidence_interval(i);
+            clients_rate.push(ClientRate {
+                uid: user_card.uid.clone(),
+                rate: self.rate(i),
+                share: Some(self.share(i)),
+                ci_low: Some(low),
+                ci_high: Some(high),
+            });
+        }
+        clients_rate
+    }
+
+    fn progress(&self, user_cards: &[CardsInfo]) -> RatingProgress {
+        RatingProgress {
+            samples: self.total,
+            population: self.population,
+            clients_rate: self.clients_rate(user_cards),
+        }
+    }
+}
+
+// 部分Fisher–Yates洗牌，从deck中无放回地抽取count张牌，抽出的牌换到deck的前面
+// deck在多次调用间保持打乱后的状态，每次的代价只与count有关，调用前用deck_valid检查张数
+pub fn draw_cards<'a, R: RngCore>(rng: &mut R, deck: &'a mut [Card], count: usize) -> &'a [Card] {
+    for i in 0..count {
+        // 乘法映射到[i, deck.len())，偏差不超过52/2^32，可以忽略
+        let span = (deck.len() - i) as u64;
+        let j = i + ((rng.next_u32() as u64 * span) >> 32) as usize;
+        deck.swap(i, j);
+    }
+    &deck[..count]
+}
+
+// 穷举剩余的0到2张公共牌，不计先后，每种组合回调一次
+pub(crate) fn for_each_runout(deck: &[Card], remain_card: usize, f: &mut dyn FnMut(&[Card])) {
+    match remain_card {
+        0 => f(&[]),
+        1 => deck.iter().for_each(|x| f(&[*x])),
+        _ => {
+            for i in 0..deck.len() {
+                for j in i + 1..deck.len() {
+                    f(&[deck[i], deck[j]]);
+                }
+            }
+        }
+    }
+}
+
+// 按顺序发0到2张牌给不同的位置(如两组公共牌、不同玩家)，先后不同算不同的组合
+pub(crate) fn for_each_ordered_runout(deck: &[Card], count: usize, f: &mut dyn FnMut(&[Card])) {
+    match count {
+        0 => f(&[]),
+        1 => deck.iter().for_each(|x| f(&[*x])),
+        _ => {
+            for i in 0..deck.len() {
+                for j in 0..deck.len() {
+                    if i != j {
+                        f(&[deck[i], deck[j]]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 从deck中随机抽取remain_card张牌补全公共牌
+pub fn sample_board<R: RngCore>(
+    rng: &mut R,
+    board: Hand,
+    deck: &mut [Card],
+    remain_card: usize,
+) -> Hand {
+    draw_cards(rng, deck, remain_card)
+        .iter()
+        .fold(board, |acc, x| acc.add_card(x.id()))
+}
+
+fn add_to_win_count(user_cards: &[CardsInfo], new_board: Hand, tally: &mut RatingTally) {
+    let max_value_indexes = showdown_winners(user_cards, new_board);
+    let temp = if max_value_indexes.len() > 1 {
+        &mut tally.draw
+    } else {
+        &mut tally.win
+    };
+    for &i in &max_value_indexes {
+        temp[i] += 1;
+        tally.share[i] += 1.0 / max_value_indexes.len() as f64;
+    }
+    tally.total += 1;
+}
+
+// 摊牌时牌力最大的玩家下标，平局时有多个
+pub fn showdown_winners(user_cards: &[CardsInfo], new_board: Hand) -> Vec<usize> {
     let mut max_evaluate: u16 = 0;
-    let mut max_value_uids = Vec::new();
+    let mut max_value_indexes = Vec::new();
     // 组合全部的牌，进行计算
-    user_cards.iter().for_each(|user_card| {
+    user_cards.iter().enumerate().for_each(|(i, user_card)| {
         let evaluate_hand = user_card.hands + new_board;
         let value = evaluate_hand.evaluate();
         if value > max_evaluate {
-            max_value_uids.clear();
-            max_value_uids.push(user_card.uid);
+            max_value_indexes.clear();
+            max_value_indexes.push(i);
             max_evaluate = value;
         } else if value == max_evaluate {
-            max_value_uids.push(user_card.uid);
+            max_value_indexes.push(i);
         }
     });
-    let mut temp = win_count_by_uid;
-    if max_value_uids.len() > 1 {
-        temp = draw_count_by_uid;
-        *draw_count += 1;
-    }
-    for uid in max_value_uids {
-        if let Some(x) = temp.get(uid) {
-            temp.insert(uid.clone(), x + 1);
-        } else {
-            temp.insert(uid.clone(), 1u64);
+    max_value_indexes
+}
+
+// 组合数表，binomial_table()[n][k] = C(n, k)
+pub(crate) fn binomial_table() -> Vec<[u64; MAX_ENUMERATE + 1]> {
+    let mut table = vec![[0u64; MAX_ENUMERATE + 1]; NUMBER_OF_CARDS + 1];
+    table[0][0] = 1;
+    for n in 1..=NUMBER_OF_CARDS {
+        table[n][0] = 1;
+        for k in 1..=MAX_ENUMERATE {
+            table[n][k] = table[n - 1][k - 1] + table[n - 1][k];
         }
     }
+    table
 }
 
-fn get_index(
-    alive_card_index: &mut Vec<i32>,
-    current_index: usize,
-    alive_cards_len: i32,
-    used_cards: &mut HashSet<i32>,
-    can_remove: &bool,
-) -> (bool, bool) {
-    // 第一次的时候不能remove
-    if *can_remove && alive_card_index[current_index] >= 0 {
-        used_cards.remove(&alive_card_index[current_index]);
-    }
-
-    alive_card_index[current_index] += 1;
-    // 在alive_card_index内已经存在对应的牌了
-    while alive_card_index[current_index] < alive_cards_len {
-        if let Some(_) = used_cards.get(&alive_card_index[current_index]) {
-            alive_card_index[current_index] += 1;
-            continue;
-        }
-        break;
-    }
-    let mut finish = false;
-    let mut return_to_previous = false;
-    if alive_card_index[current_index] >= alive_cards_len {
-        // 选择的第0号元素已经超了
-        if current_index == 0 {
-            finish = true;
-        } else {
-            // 恢复从0开始
-            alive_card_index[current_index] = -1;
-            // 此轮结束，从上层继续
-            return_to_previous = true;
+// 将序号转换为组合（组合数系统），结果为alive_cards的下标
+pub(crate) fn unrank_combination(
+    table: &[[u64; MAX_ENUMERATE + 1]],
+    n: usize,
+    mut rank: u64,
+    picked: &mut [usize],
+) {
+    for j in (1..=picked.len()).rev() {
+        // 二分查找最大的c使得C(c, j) <= rank
+        let (mut low, mut high) = (j - 1, n - 1);
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if table[mid][j] <= rank {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
         }
-    } else {
-        used_cards.insert(alive_card_index[current_index]);
+        rank -= table[low][j];
+        picked[j - 1] = low;
     }
-    (finish, return_to_previous)
 }
 
-fn convert(req: &CalculateRatingReq) -> Vec<CardsInfo> {
+pub(crate) fn coprime_stride(population: u64) -> u64 {
+    let mut stride = ((population as f64 * 0.618_033_988_7) as u64).max(1);
+    while gcd(stride, population) != 1 {
+        stride += 1;
+    }
+    stride
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// 固定采样次数用于复现结果，不能再按精度或时间停止
+fn samples_valid(req: &CalculateRatingReq) -> Result<(), String> {
+    let Some(samples) = req.samples else {
+        return Ok(());
+    };
+    if req.exact || req.target_std_err.is_some() || req.time_budget_ms.is_some() {
+        return Err(
+            "samples cannot be used with exact, target_std_err or time_budget_ms".to_string(),
+        );
+    }
+    if !(1..=MAX_TARGET_SAMPLES).contains(&samples) {
+        return Err(format!(
+            "samples should be between 1 and {}",
+            MAX_TARGET_SAMPLES
+        ));
+    }
+    Ok(())
+}
+
+// 死牌和玩家太多时，剩余的牌可能不够发完
+pub(crate) fn deck_valid(alive: usize, cards_to_deal: usize) -> Result<(), String> {
+    if alive < cards_to_deal {
+        return Err(format!(
+            "not enough cards to deal: {} alive, {} needed",
+            alive, cards_to_deal
+        ));
+    }
+    Ok(())
+}
+
+fn convert(req: &CalculateRatingReq) -> Vec<CardsInfo<'_>> {
     let mut cards = Vec::new();
     req.clients.iter().for_each(|x| {
-        let hand = x.hands[1].parse::<Hand>().unwrap() + x.hands[0].parse::<Hand>().unwrap();
         let card_info = CardsInfo {
-            hands: hand,
+            hands: x.hands.iter().copied().collect::<CardSet>().hand(),
             uid: &x.uid,
         };
         cards.push(card_info);
@@ -395,16 +741,6 @@ pub const NUMBER_OF_RANKS: usize = 13;
 /// number of ranks
 pub const NUMBER_OF_CARDS: usize = 4 * NUMBER_OF_RANKS;
 
-fn compute_alive_cards(mask: u64) -> Vec<usize> {
-    let mut result = Vec::new();
-    for i in 0..NUMBER_OF_CARDS {
-        if (CARDS[i].1 & mask) == 0 {
-            result.push(i);
-        }
-    }
-    result
-}
-
 pub const CARDSSTRING: [&str; NUMBER_OF_CARDS] = [
     "2s", "2h", "2c", "2d", "3s", "3h", "3c", "3d", "4s", "4h", "4c", "4d", "5s", "5h", "5c", "5d",
     "6s", "6h", "6c", "6d", "7s", "7h", "7c", "7d", "8s", "8h", "8c", "8d", "9s", "9h", "9c", "9d",
@@ -1,23 +1,46 @@
 #[cfg(test)]
 mod tests {
+    use crate::models::card::{parse_cards, Board, Card, CardNotation, CardSet, NotatedCard};
+    use crate::models::error_model;
     use crate::models::model::{
-        CalculateOutsReq, CalculateOutsRsp, CalculateRatingReq, CalculateRatingRsp, UserCards,
+        CalculateOutsReq, CalculateOutsRsp, CalculateRatingReq, CalculateRatingRsp,
+        CalculateTimelineReq, UserCards,
     };
-    use crate::services::evaluator::{CalculateRating, Evaluator};
+    use crate::services::evaluator::{
+        binomial_table, coprime_stride, sample_board, unrank_combination, CalculateRating,
+        Evaluator, ProgressClock, RatingTally, PROGRESS_INTERVAL,
+    };
+    use holdem_hand_evaluator::Hand;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+    use std::collections::HashSet;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    fn cards(s: &str) -> [Card; 2] {
+        let cards = parse_cards(s).unwrap();
+        [cards[0], cards[1]]
+    }
 
     #[tokio::test]
     async fn test_calculate_rating() {
         let mut req = CalculateRatingReq {
             clients: vec![],
-            deal_cards: vec![],
+            deal_cards: Board::default(),
             dead_cards: vec![],
+            exact: false,
+            target_std_err: None,
+            time_budget_ms: None,
+            seed: None,
+            samples: None,
+            second_board: None,
         };
         req.clients.push(UserCards {
-            hands: ["As".to_string(), "Ks".to_string()],
+            hands: cards("AsKs"),
             uid: "1".to_string(),
         });
         req.clients.push(UserCards {
-            hands: ["2s".to_string(), "Ts".to_string()],
+            hands: cards("2sTs"),
             uid: "2".to_string(),
         });
         // req.deal_cards.push("Ac".to_string());
@@ -38,26 +61,406 @@ mod tests {
     async fn test_calculate_rating2() {
         let mut req = CalculateRatingReq {
             clients: vec![],
-            deal_cards: vec![],
+            deal_cards: Board::default(),
+            dead_cards: vec![],
+            exact: false,
+            target_std_err: None,
+            time_budget_ms: None,
+            seed: None,
+            samples: None,
+            second_board: None,
         };
         req.clients.push(UserCards {
-            hands: ["3c".to_string(), "8c".to_string()],
+            hands: cards("3c8c"),
             uid: "1".to_string(),
         });
         req.clients.push(UserCards {
-            hands: ["Td".to_string(), "8d".to_string()],
+            hands: cards("Td8d"),
             uid: "2".to_string(),
         });
         req.clients.push(UserCards {
-            hands: ["Qc".to_string(), "5h".to_string()],
+            hands: cards("Qc5h"),
             uid: "3".to_string(),
         });
-        req.deal_cards.push("6h".to_string());
-        req.deal_cards.push("9s".to_string());
-        req.deal_cards.push("7c".to_string());
+        req.deal_cards = "6h9s7c".parse().unwrap();
         // req.deal_cards.push("Kc".to_string());
         // req.deal_cards.push("Kd".to_string());
         let evaluator = Evaluator {};
         let rsp: CalculateRatingRsp = evaluator.calculate_rating(req).await;
     }
+
+    #[tokio::test]
+    async fn test_calculate_timeline() {
+        let req = CalculateTimelineReq {
+            clients: vec![
+                UserCards {
+                    hands: cards("AsAd"),
+                    uid: "1".to_string(),
+                },
+                UserCards {
+                    hands: cards("KsKd"),
+                    uid: "2".to_string(),
+                },
+            ],
+            deal_cards: "2c7hKc9d3s".parse().unwrap(),
+            dead_cards: vec![],
+            notation: CardNotation::Id,
+        };
+        let evaluator = Evaluator {};
+        let rsp = evaluator.calculate_timeline(req).await;
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.streets.len(), 4);
+        assert_eq!(rsp.streets[1].outs.len(), 2);
+        assert!(rsp.streets[3].outs.is_empty());
+        let outs = &rsp.streets[1].outs;
+        assert!(outs
+            .iter()
+            .all(|x| x.cards.iter().all(|c| matches!(c, NotatedCard::Id(_)))));
+        // river时K的三条赢
+        assert_eq!(rsp.streets[3].clients_rate[1].rate, 10000);
+    }
+
+    #[tokio::test]
+    async fn test_calculate_rating_target_std_err() {
+        let req = CalculateRatingReq {
+            clients: vec![
+                UserCards {
+                    hands: cards("AsAd"),
+                    uid: "1".to_string(),
+                },
+                UserCards {
+                    hands: cards("KsKd"),
+                    uid: "2".to_string(),
+                },
+            ],
+            deal_cards: Board::default(),
+            dead_cards: vec![],
+            exact: false,
+            target_std_err: Some(100),
+            time_budget_ms: None,
+            seed: None,
+            samples: None,
+            second_board: None,
+        };
+        let evaluator = Evaluator {};
+        let rsp = evaluator.calculate_rating(req).await;
+        assert_eq!(rsp.code, 0);
+        assert!(rsp.samples < 11000);
+        for client_rate in &rsp.clients_rate {
+            assert!(client_rate.ci_low.unwrap() <= client_rate.rate);
+            assert!(client_rate.rate <= client_rate.ci_high.unwrap());
+            assert!(client_rate.ci_high.unwrap() - client_rate.ci_low.unwrap() <= 400);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_calculate_rating_seed() {
+        let new_req = |seed: Option<u64>| CalculateRatingReq {
+            clients: vec![
+                UserCards {
+                    hands: cards("AsKs"),
+                    uid: "1".to_string(),
+                },
+                UserCards {
+                    hands: cards("2sTs"),
+                    uid: "2".to_string(),
+                },
+            ],
+            deal_cards: Board::default(),
+            dead_cards: vec![],
+            exact: false,
+            target_std_err: None,
+            time_budget_ms: None,
+            seed,
+            samples: None,
+            second_board: None,
+        };
+        let evaluator = Evaluator {};
+        let first = evaluator.calculate_rating(new_req(None)).await;
+        let second = evaluator.calculate_rating(new_req(Some(first.seed))).await;
+        assert_eq!(first.seed, second.seed);
+        assert_eq!(first.samples, second.samples);
+        for (a, b) in first.clients_rate.iter().zip(second.clients_rate.iter()) {
+            assert_eq!(a.rate, b.rate);
+        }
+    }
+
+    #[test]
+    fn test_calculate_rating_replay() {
+        // 按精度停止的结果，传入返回的seed和samples可以复现
+        let mut req = exact_req(&["AsKs", "2sTs"], "");
+        req.exact = false;
+        req.target_std_err = Some(100);
+        let first = Evaluator {}.rating(req);
+        assert_eq!(first.code, 0);
+        let mut req = exact_req(&["AsKs", "2sTs"], "");
+        req.exact = false;
+        req.seed = Some(first.seed);
+        req.samples = Some(first.samples);
+        let second = Evaluator {}.rating(req);
+        assert_eq!(second.samples, first.samples);
+        assert!(second.completed);
+        for (a, b) in first.clients_rate.iter().zip(second.clients_rate.iter()) {
+            assert_eq!(a.rate, b.rate);
+        }
+
+        let mut req = exact_req(&["AsKs", "2sTs"], "");
+        req.samples = Some(1000);
+        assert_eq!(Evaluator {}.rating(req).code, error_model::ERROR_INVALID);
+        let mut req = exact_req(&["AsKs", "2sTs"], "");
+        req.exact = false;
+        req.samples = Some(0);
+        assert_eq!(Evaluator {}.rating(req).code, error_model::ERROR_INVALID);
+    }
+
+    #[test]
+    fn test_sample_board() {
+        let board: Board = "Ah7d2c".parse().unwrap();
+        let dead = parse_cards("KsQs").unwrap();
+        let mut known = board.card_set();
+        known.extend(dead.iter().copied());
+        let alive = known.complement();
+        let mut deck: Vec<Card> = alive.iter().collect();
+        let hand = board.hand();
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let mut drawn_all = CardSet::default();
+        for _ in 0..1000 {
+            let new_board = sample_board(&mut rng, hand, &mut deck, 2);
+            assert_eq!(new_board.len(), 5);
+            assert_eq!(deck.len(), 47);
+            // 抽出的两张在deck前面，互不相同，且不是公共牌或dead_cards
+            let drawn = &deck[..2];
+            assert_ne!(drawn[0], drawn[1]);
+            assert!(drawn.iter().all(|x| !known.contains(*x)));
+            assert!(drawn.iter().all(|x| new_board.contains(x.id())));
+            // deck仍是剩余牌的一个排列
+            assert_eq!(deck.iter().copied().collect::<CardSet>(), alive);
+            drawn_all.extend(drawn.iter().copied());
+        }
+        assert_eq!(drawn_all, alive);
+    }
+
+    // 采样速度对比: cargo test --release bench_sample_board -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_sample_board() {
+        let board = Hand::new();
+        let alive_cards: Vec<Card> = Card::all().skip(4).collect();
+        let loops = 2_000_000;
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let start = Instant::now();
+        let mut checksum = 0;
+        for _ in 0..loops {
+            // 原先的拒绝采样
+            let mut new_board = board;
+            let mut i = 0;
+            while i < 5 {
+                let random_number = rng.gen_range(0..alive_cards.len());
+                if new_board.contains(alive_cards[random_number].id()) {
+                    continue;
+                }
+                new_board = new_board.add_card(alive_cards[random_number].id());
+                i += 1;
+            }
+            checksum += new_board.len();
+        }
+        let rejection = start.elapsed();
+        let mut deck = alive_cards.clone();
+        let start = Instant::now();
+        for _ in 0..loops {
+            checksum += sample_board(&mut rng, board, &mut deck, 5).len();
+        }
+        let fisher_yates = start.elapsed();
+        println!(
+            "rejection: {:.0} boards/s, fisher-yates: {:.0} boards/s, checksum {}",
+            loops as f64 / rejection.as_secs_f64(),
+            loops as f64 / fisher_yates.as_secs_f64(),
+            checksum
+        );
+    }
+
+    #[test]
+    fn test_unrank_combination() {
+        // 每个序号对应不同的递增组合，覆盖全部C(n, k)种
+        let table = binomial_table();
+        for (n, k) in [(7, 3), (10, 1), (12, 5), (5, 5)] {
+            let mut seen = HashSet::new();
+            let mut picked = vec![0usize; k];
+            for rank in 0..table[n][k] {
+                unrank_combination(&table, n, rank, &mut picked);
+                assert!(picked.windows(2).all(|x| x[0] < x[1]));
+                assert!(picked.iter().all(|x| *x < n));
+                seen.insert(picked.clone());
+            }
+            assert_eq!(seen.len() as u64, table[n][k]);
+        }
+    }
+
+    #[test]
+    fn test_coprime_stride() {
+        // 步长与组合总数互质，跳跃一轮恰好访问每个序号一次
+        for population in [1u64, 2, 44, 990, 1081, 15180] {
+            let stride = coprime_stride(population);
+            let visited: HashSet<u64> = (0..population).map(|i| i * stride % population).collect();
+            assert_eq!(visited.len() as u64, population);
+        }
+    }
+
+    // 逐个组合直接比牌，作为穷举结果的对照
+    fn naive_rates(hands: &[&str], board: &str) -> Vec<u64> {
+        let board: Board = board.parse().unwrap();
+        let mut known = board.card_set();
+        let hands: Vec<Hand> = hands
+            .iter()
+            .map(|x| {
+                let cards = parse_cards(x).unwrap();
+                known.extend(cards.iter().copied());
+                cards
+                    .iter()
+                    .fold(Hand::new(), |acc, c| acc.add_card(c.id()))
+            })
+            .collect();
+        let alive: Vec<Card> = known.complement().iter().collect();
+        let mut win = vec![0u64; hands.len()];
+        let mut draw = vec![0u64; hands.len()];
+        let mut total = 0u64;
+        let mut count = |extra: &[Card]| {
+            let board = extra
+                .iter()
+                .fold(board.hand(), |acc, c| acc.add_card(c.id()));
+            let values: Vec<u16> = hands.iter().map(|h| (*h + board).evaluate()).collect();
+            let best = *values.iter().max().unwrap();
+            let winners: Vec<usize> = (0..values.len()).filter(|i| values[*i] == best).collect();
+            for i in &winners {
+                if winners.len() > 1 {
+                    draw[*i] += 1;
+                } else {
+                    win[*i] += 1;
+                }
+            }
+            total += 1;
+        };
+        if board.len() == 4 {
+            alive.iter().for_each(|x| count(&[*x]));
+        } else {
+            for i in 0..alive.len() {
+                for j in i + 1..alive.len() {
+                    count(&[alive[i], alive[j]]);
+                }
+            }
+        }
+        (0..hands.len())
+            .map(|i| (win[i] * 10000 + draw[i] * 5000) / total)
+            .collect()
+    }
+
+    fn exact_req(hands: &[&str], board: &str) -> CalculateRatingReq {
+        CalculateRatingReq {
+            clients: hands
+                .iter()
+                .enumerate()
+                .map(|(i, x)| UserCards {
+                    hands: cards(x),
+                    uid: i.to_string(),
+                })
+                .collect(),
+            deal_cards: board.parse().unwrap(),
+            dead_cards: vec![],
+            exact: true,
+            target_std_err: None,
+            time_budget_ms: None,
+            seed: None,
+            samples: None,
+            second_board: None,
+        }
+    }
+
+    #[test]
+    fn test_enumerate_matches_naive() {
+        // 转牌和翻牌上穷举的结果与逐个组合直接比牌一致
+        for (hands, board) in [
+            (vec!["AsKs", "QhQd", "7c8c"], "Kd9c6c"),
+            (vec!["AsKs", "QhQd", "7c8c"], "Kd9c6cQs"),
+            (vec!["AhKd", "AcKc"], "2h3h4d"),
+        ] {
+            let rsp = Evaluator {}.rating(exact_req(&hands, board));
+            assert_eq!(rsp.code, 0);
+            assert!(rsp.completed && rsp.exact);
+            let rates: Vec<u64> = rsp.clients_rate.iter().map(|x| x.rate).collect();
+            assert_eq!(rates, naive_rates(&hands, board));
+        }
+    }
+
+    #[test]
+    fn test_progress_clock() {
+        let tally = RatingTally::new(2, 0);
+        // 超时后不再回调，直接停止
+        let mut clock = ProgressClock::new(Some(Instant::now()));
+        assert!(clock.check(&tally, &mut |_| panic!("should not report after deadline")));
+        assert!(clock.stopped);
+
+        // 回调返回false(客户端已断开)时停止
+        let mut clock = ProgressClock::new(Some(Instant::now() + Duration::from_secs(60)));
+        let mut reports = 0;
+        assert!(!clock.check(&tally, &mut |_| {
+            reports += 1;
+            true
+        }));
+        assert_eq!(reports, 0);
+        thread::sleep(PROGRESS_INTERVAL);
+        assert!(!clock.check(&tally, &mut |_| {
+            reports += 1;
+            true
+        }));
+        assert_eq!(reports, 1);
+        thread::sleep(PROGRESS_INTERVAL);
+        assert!(clock.check(&tally, &mut |_| false));
+        assert!(clock.stopped);
+    }
+
+    #[test]
+    fn test_confidence_interval_contains_rate() {
+        // 穷举完时区间宽度为0，rate向下取整为6666，四舍五入的区间会是[6667, 6667]
+        let mut tally = RatingTally::new(1, 3);
+        tally.win[0] = 2;
+        tally.total = 3;
+        assert_eq!(tally.rate(0), 6666);
+        assert_eq!(tally.confidence_interval(0), (6666, 6667));
+    }
+
+    #[test]
+    fn test_rating_cancelled() {
+        // 翻牌前穷举在超时后返回已算的部分，并标记未算完
+        let req = exact_req(&["AsKs", "QhQd"], "");
+        let rsp = Evaluator {}.calculate_rating_progress(req, Duration::ZERO, &mut |_| true);
+        assert_eq!(rsp.code, 0);
+        assert!(!rsp.completed && !rsp.exact);
+        assert!(rsp.samples > 0 && rsp.samples < 1_712_304);
+    }
+
+    #[test]
+    fn test_rating_not_enough_cards() {
+        // 死牌太多，剩余的牌不够发完公共牌
+        for (board, exact, alive) in [("", false, 4), ("Kd9c6c", true, 1)] {
+            let mut req = exact_req(&["AsKs", "QhQd"], board);
+            req.exact = exact;
+            let mut known = req.deal_cards.card_set();
+            known.extend(parse_cards("AsKsQhQd").unwrap());
+            req.dead_cards = known.complement().iter().skip(alive).collect();
+            let rsp = Evaluator {}.rating(req);
+            assert_eq!(rsp.code, error_model::ERROR_INVALID);
+        }
+    }
+
+    #[test]
+    fn test_rating_samples_deadline() {
+        // 固定采样次数也受时间上限约束，超时返回已算的部分
+        let mut req = exact_req(&["AsKs", "QhQd"], "");
+        req.exact = false;
+        req.samples = Some(10_000_000);
+        let rsp = Evaluator {}.calculate_rating_progress(req, Duration::ZERO, &mut |_| true);
+        assert_eq!(rsp.code, 0);
+        assert!(!rsp.completed && !rsp.exact);
+        assert!(rsp.samples > 0 && rsp.samples < 10_000_000);
+    }
 }
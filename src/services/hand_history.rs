@@ -0,0 +1,254 @@
+use crate::models::card::{parse_cards, Board, Card, CardNotation};
+use crate::models::error_model;
+use crate::models::model::{
+    AnnotateHistoryReq, AnnotateHistoryRsp, AnnotatedHand, CalculateTimelineReq, HandHistory,
+    HistoryPlayer, SkippedHand, Street, UserCards,
+};
+use crate::services::evaluator::Evaluator;
+
+// 每次请求最多标注的牌局数，更多的牌局用命令行处理
+const MAX_HANDS: usize = 200;
+
+impl Evaluator {
+    pub fn annotate_history(&self, req: AnnotateHistoryReq) -> AnnotateHistoryRsp {
+        let mut rsp = AnnotateHistoryRsp {
+            code: 0,
+            hands: vec![],
+            skipped: vec![],
+            msg: "".to_string(),
+        };
+        let mut hands = req.hands;
+        hands.extend(parse_pokerstars(&req.pokerstars));
+        if hands.is_empty() {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = "req has no hand history".to_string();
+            return rsp;
+        }
+        if hands.len() > MAX_HANDS {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = format!("req has more than {} hands", MAX_HANDS);
+            return rsp;
+        }
+        for hand in &hands {
+            match self.annotate_hand(hand, req.notation) {
+                Ok(annotated) => rsp.hands.push(annotated),
+                Err(reason) => rsp.skipped.push(SkippedHand {
+                    hand_id: hand.hand_id.clone(),
+                    reason,
+                }),
+            }
+        }
+        return rsp;
+    }
+
+    // 只标注全下或摊牌且至少两个玩家亮牌的牌局，否则返回跳过的原因
+    pub fn annotate_hand(
+        &self,
+        hand: &HandHistory,
+        notation: CardNotation,
+    ) -> Result<AnnotatedHand, String> {
+        let mut clients = vec![];
+        let mut dead_cards = vec![];
+        let mut all_in = false;
+        for player in &hand.players {
+            let Some(hands) = player.hands else {
+                continue;
+            };
+            // 弃牌玩家亮过的牌不参与比牌，当作死牌
+            if player.folded || !(player.all_in || player.showdown) {
+                dead_cards.extend(hands);
+                continue;
+            }
+            all_in |= player.all_in;
+            clients.push(UserCards {
+                hands,
+                uid: player.uid.clone(),
+            });
+        }
+        if clients.len() < 2 {
+            return Err("less than 2 known hands at showdown or all-in".to_string());
+        }
+        if hand.board.len() != 5 {
+            return Err("board is not complete".to_string());
+        }
+        let timeline_req = CalculateTimelineReq {
+            clients: clients.clone(),
+            deal_cards: hand.board.clone(),
+            dead_cards,
+            notation,
+        };
+        let timeline_rsp = self.timeline(timeline_req);
+        if timeline_rsp.code != 0 {
+            return Err(timeline_rsp.msg);
+        }
+        let situation = if all_in && hand.all_in_street.is_some() {
+            "all_in"
+        } else {
+            "showdown"
+        };
+        return Ok(AnnotatedHand {
+            hand_id: hand.hand_id.clone(),
+            situation: situation.to_string(),
+            all_in_street: hand.all_in_street.filter(|_| all_in),
+            clients,
+            board: hand.board.clone(),
+            streets: timeline_rsp.streets,
+        });
+    }
+}
+
+// 解析PokerStars的文本牌局记录，一段文本可以包含多手牌，无法识别的行忽略
+pub fn parse_pokerstars(text: &str) -> Vec<HandHistory> {
+    let mut hands = vec![];
+    let mut current: Option<PokerStarsHand> = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with("PokerStars Hand #") || line.starts_with("PokerStars Game #") {
+            if let Some(hand) = current.take() {
+                hands.push(hand.finish());
+            }
+            current = Some(PokerStarsHand::new(line));
+            continue;
+        }
+        if let Some(hand) = current.as_mut() {
+            hand.parse_line(line);
+        }
+    }
+    if let Some(hand) = current {
+        hands.push(hand.finish());
+    }
+    return hands;
+}
+
+struct PokerStarsHand {
+    history: HandHistory,
+    street: Street,
+    board: Vec<Card>,
+    summary: bool,
+}
+
+impl PokerStarsHand {
+    fn new(header: &str) -> PokerStarsHand {
+        // PokerStars Hand #123456789: Hold'em No Limit ...
+        let hand_id = header
+            .split_once('#')
+            .map(|(_, x)| x.split(':').next().unwrap_or("").trim().to_string())
+            .unwrap_or_default();
+        return PokerStarsHand {
+            history: HandHistory {
+                hand_id,
+                ..Default::default()
+            },
+            street: Street::Preflop,
+            board: vec![],
+            summary: false,
+        };
+    }
+
+    fn parse_line(&mut self, line: &str) {
+        if let Some(marker) = line.strip_prefix("*** ") {
+            match marker.split(" ***").next().unwrap_or("") {
+                "FLOP" => self.street = Street::Flop,
+                "TURN" => self.street = Street::Turn,
+                "RIVER" => self.street = Street::River,
+                "SUMMARY" => self.summary = true,
+                _ => {}
+            }
+            // *** TURN *** [7h 8h 2c] [3s]，最后一组是新发的牌
+            if self.street != Street::Preflop && !self.summary {
+                if let Some(cards) = brackets(line).last().and_then(|x| parse_cards(x).ok()) {
+                    self.board.extend(cards);
+                }
+            }
+            return;
+        }
+        if self.summary {
+            if let Some(rest) = line.strip_prefix("Board ") {
+                if let Some(cards) = brackets(rest).first().and_then(|x| parse_cards(x).ok()) {
+                    self.board = cards;
+                }
+            }
+            return;
+        }
+        // Seat 1: Alice ($2 in chips)，只在发手牌之前出现
+        if let Some(rest) = line.strip_prefix("Seat ") {
+            if let Some((_, seat)) = rest.split_once(": ") {
+                if let Some((name, _)) = seat.rsplit_once(" (") {
+                    if self.player_mut(name).is_none() {
+                        self.history.players.push(HistoryPlayer {
+                            uid: name.to_string(),
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+            return;
+        }
+        // Dealt to Alice [As Kd]
+        if let Some(rest) = line.strip_prefix("Dealt to ") {
+            let name = rest.rsplit_once(" [").map(|(x, _)| x).unwrap_or(rest);
+            let hands = hole_cards(rest);
+            if let Some(player) = self.player_mut(name) {
+                player.hands = hands.or(player.hands);
+            }
+            return;
+        }
+        let Some(name) = self.action_player(line) else {
+            return;
+        };
+        let action = &line[name.len() + 2..];
+        if action.ends_with("and is all-in") && self.history.all_in_street.is_none() {
+            self.history.all_in_street = Some(self.street);
+        }
+        let player = self.player_mut(&name).unwrap();
+        if action.starts_with("folds") {
+            player.folded = true;
+        } else if action.starts_with("shows") {
+            player.showdown = true;
+            player.hands = hole_cards(action).or(player.hands);
+        } else if action.ends_with("and is all-in") {
+            player.all_in = true;
+        }
+    }
+
+    // 玩家名可能包含空格和冒号，取最长的匹配
+    fn action_player(&self, line: &str) -> Option<String> {
+        return self
+            .history
+            .players
+            .iter()
+            .filter(|x| {
+                line.strip_prefix(x.uid.as_str())
+                    .is_some_and(|rest| rest.starts_with(": "))
+            })
+            .max_by_key(|x| x.uid.len())
+            .map(|x| x.uid.clone());
+    }
+
+    fn player_mut(&mut self, name: &str) -> Option<&mut HistoryPlayer> {
+        return self.history.players.iter_mut().find(|x| x.uid == name);
+    }
+
+    fn finish(mut self) -> HandHistory {
+        // 公共牌不合法时(如两次发牌)当作没有公共牌
+        self.history.board = Board::new(self.board).unwrap_or_default();
+        return self.history;
+    }
+}
+
+fn brackets(line: &str) -> Vec<&str> {
+    return line
+        .split('[')
+        .skip(1)
+        .filter_map(|x| x.split_once(']').map(|(cards, _)| cards))
+        .collect();
+}
+
+// 只识别两张的德州手牌
+fn hole_cards(line: &str) -> Option<[Card; 2]> {
+    let cards = parse_cards(brackets(line).first()?).ok()?;
+    if cards.len() != 2 {
+        return None;
+    }
+    return Some([cards[0], cards[1]]);
+}
@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::card::CardNotation;
+    use crate::models::model::{AnnotateHistoryReq, HandHistory, Street};
+    use crate::services::evaluator::Evaluator;
+    use crate::services::hand_history::parse_pokerstars;
+
+    const POKERSTARS: &str = "PokerStars Hand #230000000001:  Hold'em No Limit ($0.01/$0.02 USD) - 2024/01/01 12:00:00 ET
+Table 'Alpha' 6-max Seat #1 is the button
+Seat 1: Alice ($2 in chips)
+Seat 2: Bob Smith ($2 in chips)
+Seat 3: Carol ($2 in chips)
+Bob Smith: posts small blind $0.01
+Carol: posts big blind $0.02
+*** HOLE CARDS ***
+Dealt to Alice [As Kd]
+Alice: raises $0.04 to $0.06
+Bob Smith: calls $0.05
+Carol: folds
+*** FLOP *** [7h 8h 2c]
+Bob Smith: bets $1.94 and is all-in
+Alice: calls $1.94 and is all-in
+*** TURN *** [7h 8h 2c] [3s]
+*** RIVER *** [7h 8h 2c 3s] [9d]
+*** SHOW DOWN ***
+Bob Smith: shows [Qs Qc] (a pair of Queens)
+Alice: shows [As Kd] (high card Ace)
+Bob Smith collected $4 from pot
+*** SUMMARY ***
+Total pot $4 | Rake $0
+Board [7h 8h 2c 3s 9d]
+Seat 1: Alice (button) showed [As Kd] and lost with high card Ace
+
+PokerStars Hand #230000000002:  Hold'em No Limit ($0.01/$0.02 USD) - 2024/01/01 12:01:00 ET
+Table 'Alpha' 6-max Seat #2 is the button
+Seat 1: Alice ($2 in chips)
+Seat 2: Bob Smith ($2 in chips)
+*** HOLE CARDS ***
+Dealt to Alice [2d 7c]
+Alice: folds
+*** SUMMARY ***
+";
+
+    #[test]
+    fn test_parse_pokerstars() {
+        let hands = parse_pokerstars(POKERSTARS);
+        assert_eq!(hands.len(), 2);
+        let hand = &hands[0];
+        assert_eq!(hand.hand_id, "230000000001");
+        assert_eq!(hand.players.len(), 3);
+        assert_eq!(hand.board.to_string(), "7h8h2c3s9d");
+        assert_eq!(hand.all_in_street, Some(Street::Flop));
+        let bob = &hand.players[1];
+        assert_eq!(bob.uid, "Bob Smith");
+        assert!(bob.all_in && bob.showdown);
+        assert_eq!(bob.hands.unwrap()[0].to_string(), "Qs");
+        assert!(hand.players[2].folded);
+        assert!(hands[1].board.is_empty());
+    }
+
+    #[test]
+    fn test_annotate_history() {
+        let json = r#"{"hand_id":"h1","board":["Ks","Qh","2c","7d","9s"],"players":[
+            {"uid":"1","hands":["As","Ad"],"showdown":true},
+            {"uid":"2","hands":["Kd","Kc"],"showdown":true},
+            {"uid":"3","hands":["Jc","Tc"],"folded":true}]}"#;
+        let req = AnnotateHistoryReq {
+            hands: vec![serde_json::from_str::<HandHistory>(json).unwrap()],
+            pokerstars: POKERSTARS.to_string(),
+            notation: CardNotation::Short,
+        };
+        let rsp = Evaluator {}.annotate_history(req);
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.hands.len(), 2);
+        assert_eq!(rsp.skipped.len(), 1);
+        assert_eq!(rsp.skipped[0].hand_id, "230000000002");
+
+        let showdown = &rsp.hands[0];
+        assert_eq!(showdown.situation, "showdown");
+        assert_eq!(showdown.clients.len(), 2);
+        assert_eq!(showdown.streets.len(), 4);
+        // river时K的三条赢
+        assert_eq!(showdown.streets[3].clients_rate[1].rate, 10000);
+
+        let all_in = &rsp.hands[1];
+        assert_eq!(all_in.situation, "all_in");
+        assert_eq!(all_in.all_in_street, Some(Street::Flop));
+        assert_eq!(all_in.streets[3].clients_rate[0].uid, "Alice");
+        assert_eq!(all_in.streets[3].clients_rate[0].rate, 0);
+    }
+}
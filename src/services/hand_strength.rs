@@ -0,0 +1,114 @@
+use std::cmp::Ordering;
+
+use crate::models::card::{Card, CardSet, MAX_BOARD};
+use crate::models::error_model;
+use crate::models::model::{HandStrengthReq, HandStrengthRsp, UserCards};
+use crate::models::range::Range;
+use crate::services::evaluator::{for_each_runout, Evaluator};
+
+// 对手人数上限，HS按人数取幂只是近似
+const MAX_OPPONENTS: u32 = 9;
+
+const AHEAD: usize = 0;
+const TIED: usize = 1;
+const BEHIND: usize = 2;
+
+fn compare(ours: u16, theirs: u16) -> usize {
+    match ours.cmp(&theirs) {
+        Ordering::Greater => AHEAD,
+        Ordering::Equal => TIED,
+        Ordering::Less => BEHIND,
+    }
+}
+
+impl Evaluator {
+    // Billings等人的HS、PPOT、NPOT，对手范围内每种组合等权重，穷举剩余公共牌
+    pub fn hand_strength(&self, req: HandStrengthReq) -> HandStrengthRsp {
+        let mut rsp = HandStrengthRsp {
+            code: 0,
+            hs: 0,
+            ppot: 0,
+            npot: 0,
+            ehs: 0,
+            ehs_net: 0,
+            combos: 0,
+            msg: "".to_string(),
+        };
+        let range = match &req.range {
+            Some(range) => range.parse::<Range>(),
+            None => Ok(Range::full()),
+        };
+        let Ok(range) = range else {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = "range is invalid".to_string();
+            return rsp;
+        };
+        let mut known = req.deal_cards.card_set();
+        known.extend(req.dead_cards.iter().copied());
+        let duplicated = req.hands.iter().any(|x| !known.insert(*x));
+        if duplicated || req.deal_cards.len() < 3 || !(1..=MAX_OPPONENTS).contains(&req.opponents) {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = format!(
+                "req has duplicates, or deal cards less than 3, or opponents not between 1 and {}",
+                MAX_OPPONENTS
+            );
+            return rsp;
+        }
+        let range = range.without(known);
+        if range.is_empty() {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = "range has no combos left".to_string();
+            return rsp;
+        }
+        let client = UserCards {
+            hands: req.hands,
+            uid: "".to_string(),
+        };
+        let (board, alive_cards) =
+            self.get_board_and_alive_cards(&req.deal_cards, &req.dead_cards, &[client]);
+        let ours = board + CardSet::from_iter(req.hands).hand();
+        let ours_now = ours.evaluate();
+        let remain_card = MAX_BOARD - req.deal_cards.len();
+        let mut hs = [0u64; 3];
+        let mut hp = [[0u64; 3]; 3];
+        let mut hp_total = [0u64; 3];
+        for opp in range.combos() {
+            let theirs = board + CardSet::from_iter(*opp).hand();
+            let index = compare(ours_now, theirs.evaluate());
+            hs[index] += 1;
+            let deck: Vec<Card> = alive_cards
+                .iter()
+                .filter(|x| !opp.contains(x))
+                .copied()
+                .collect();
+            for_each_runout(&deck, remain_card, &mut |runout| {
+                let runout = CardSet::from_iter(runout.iter().copied()).hand();
+                let ours = (ours + runout).evaluate();
+                let theirs = (theirs + runout).evaluate();
+                hp[index][compare(ours, theirs)] += 1;
+                hp_total[index] += 1;
+            });
+        }
+        let total = (hs[AHEAD] + hs[TIED] + hs[BEHIND]) as f64;
+        let hs = (hs[AHEAD] as f64 + hs[TIED] as f64 / 2.0) / total;
+        let ratio = |num: f64, den: f64| if den > 0.0 { num / den } else { 0.0 };
+        let ppot = ratio(
+            hp[BEHIND][AHEAD] as f64 + hp[BEHIND][TIED] as f64 / 2.0 + hp[TIED][AHEAD] as f64 / 2.0,
+            hp_total[BEHIND] as f64 + hp_total[TIED] as f64 / 2.0,
+        );
+        let npot = ratio(
+            hp[AHEAD][BEHIND] as f64 + hp[TIED][BEHIND] as f64 / 2.0 + hp[AHEAD][TIED] as f64 / 2.0,
+            hp_total[AHEAD] as f64 + hp_total[TIED] as f64 / 2.0,
+        );
+        // 多个对手时假设各对手独立
+        let hs = hs.powi(req.opponents as i32);
+        let to_rate = |x: f64| (x * 10000.0).round() as u64;
+        rsp.hs = to_rate(hs);
+        rsp.ppot = to_rate(ppot);
+        rsp.npot = to_rate(npot);
+        rsp.ehs = to_rate(hs + (1.0 - hs) * ppot);
+        rsp.ehs_net = to_rate(hs * (1.0 - npot) + (1.0 - hs) * ppot);
+        rsp.combos = range.len() as u64;
+        return rsp;
+    }
+}
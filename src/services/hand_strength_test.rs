@@ -0,0 +1,57 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::card::parse_cards;
+    use crate::models::model::HandStrengthReq;
+    use crate::services::evaluator::Evaluator;
+
+    fn new_req(hands: &str, board: &str, range: Option<&str>) -> HandStrengthReq {
+        let cards = parse_cards(hands).unwrap();
+        HandStrengthReq {
+            hands: [cards[0], cards[1]],
+            deal_cards: board.parse().unwrap(),
+            dead_cards: vec![],
+            range: range.map(|x| x.to_string()),
+            opponents: 1,
+        }
+    }
+
+    #[test]
+    fn test_hand_strength_river() {
+        let evaluator = Evaluator {};
+        // 河牌时只有当前牌力，三条A只输给16种T8的顺子
+        let rsp = evaluator.hand_strength(new_req("AsAd", "Ah7c2d9sJh", None));
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.combos, 990);
+        assert_eq!(rsp.hs, 9838);
+        assert_eq!(rsp.ppot, 0);
+        assert_eq!(rsp.npot, 0);
+        assert_eq!(rsp.ehs, rsp.hs);
+    }
+
+    #[test]
+    fn test_hand_strength_range() {
+        let evaluator = Evaluator {};
+        // 同花听牌对AA: 当前落后，河牌补上同花就反超，对手拿Ah时只剩8张
+        // PPOT = (3 * 8 + 3 * 9) / (6 * 44)
+        let rsp = evaluator.hand_strength(new_req("KhQh", "2h7h9c3s", Some("AA")));
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.combos, 6);
+        assert_eq!(rsp.hs, 0);
+        assert_eq!(rsp.ppot, 1932);
+        assert_eq!(rsp.ehs, rsp.ppot);
+
+        let mut req = new_req("KhQh", "2h7h9c3s", Some("AA"));
+        req.opponents = 0;
+        assert_ne!(evaluator.hand_strength(req).code, 0);
+        assert_ne!(
+            evaluator.hand_strength(new_req("KhQh", "2h7h", None)).code,
+            0
+        );
+        assert_ne!(
+            evaluator
+                .hand_strength(new_req("KhQh", "2h7h9c", Some("Kh7h")))
+                .code,
+            0
+        );
+    }
+}
@@ -0,0 +1,363 @@
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::models::card::{Card, CardSet, Rank, DECK_SIZE, MAX_BOARD};
+use crate::models::error_model;
+use crate::models::model::{
+    HiLoClientRate, HiLoGame, HiLoOuts, HiLoOutsReq, HiLoOutsRsp, HiLoRatingReq, HiLoRatingRsp,
+    HiLoUserCards,
+};
+use crate::services::evaluator::{draw_cards, for_each_runout, Evaluator};
+use crate::utils::log::log_info_display;
+
+// 剩余3张及以上公共牌时的采样次数
+const HI_LO_SAMPLES: u64 = 10000;
+
+impl HiLoGame {
+    pub fn hole_cards(self) -> usize {
+        match self {
+            HiLoGame::OmahaHiLo => 4,
+            HiLoGame::HoldemHiLo => 2,
+        }
+    }
+}
+
+// 低牌中的位置，A为0，8为7，9及以上不能成低牌
+fn low_bit(rank: Rank) -> Option<u8> {
+    match rank {
+        Rank::Ace => Some(0),
+        _ if rank <= Rank::Eight => Some(rank.index() as u8 + 1),
+        _ => None,
+    }
+}
+
+fn low_mask(cards: &[Card]) -> u8 {
+    cards
+        .iter()
+        .filter_map(|x| low_bit(x.rank()))
+        .fold(0, |acc, x| acc | (1 << x))
+}
+
+// 5张不同点数的低牌掩码，先比最大的牌，所以掩码越小低牌越好
+fn lowest_five(mask: u8) -> Option<u8> {
+    if mask.count_ones() < 5 {
+        return None;
+    }
+    let mut low = 0u8;
+    let mut rest = mask;
+    for _ in 0..5 {
+        low |= rest & rest.wrapping_neg();
+        rest &= rest - 1;
+    }
+    Some(low)
+}
+
+fn pairs(cards: &[Card]) -> Vec<[Card; 2]> {
+    let mut pairs = vec![];
+    for i in 0..cards.len() {
+        for j in i + 1..cards.len() {
+            pairs.push([cards[i], cards[j]]);
+        }
+    }
+    pairs
+}
+
+fn triples(cards: &[Card]) -> Vec<[Card; 3]> {
+    let mut triples = vec![];
+    for i in 0..cards.len() {
+        for j in i + 1..cards.len() {
+            for k in j + 1..cards.len() {
+                triples.push([cards[i], cards[j], cards[k]]);
+            }
+        }
+    }
+    triples
+}
+
+// 高牌牌力，越大越好
+pub fn best_high(game: HiLoGame, hole: &[Card], board: &[Card]) -> u16 {
+    match game {
+        HiLoGame::HoldemHiLo => hole
+            .iter()
+            .chain(board)
+            .copied()
+            .collect::<CardSet>()
+            .hand()
+            .evaluate(),
+        HiLoGame::OmahaHiLo => {
+            let triples = triples(board);
+            pairs(hole)
+                .iter()
+                .flat_map(|x| {
+                    triples.iter().map(move |y| {
+                        x.iter()
+                            .chain(y)
+                            .copied()
+                            .collect::<CardSet>()
+                            .hand()
+                            .evaluate()
+                    })
+                })
+                .max()
+                .unwrap_or(0)
+        }
+    }
+}
+
+// 低牌的掩码，越小越好，不成低牌时为None
+pub fn best_low(game: HiLoGame, hole: &[Card], board: &[Card]) -> Option<u8> {
+    match game {
+        HiLoGame::HoldemHiLo => lowest_five(low_mask(hole) | low_mask(board)),
+        HiLoGame::OmahaHiLo => {
+            let triples: Vec<u8> = triples(board)
+                .iter()
+                .map(|x| low_mask(x))
+                .filter(|x| x.count_ones() == 3)
+                .collect();
+            pairs(hole)
+                .iter()
+                .map(|x| low_mask(x))
+                .filter(|x| x.count_ones() == 2)
+                .flat_map(|x| {
+                    triples
+                        .iter()
+                        .filter(move |y| x & **y == 0)
+                        .map(move |y| x | y)
+                })
+                .min()
+        }
+    }
+}
+
+// 每个玩家分到的高牌和低牌份额，以整个底池为1
+pub fn split_pot(game: HiLoGame, clients: &[HiLoUserCards], board: &[Card]) -> Vec<(f64, f64)> {
+    let highs: Vec<u16> = clients
+        .iter()
+        .map(|x| best_high(game, &x.hands, board))
+        .collect();
+    let lows: Vec<Option<u8>> = clients
+        .iter()
+        .map(|x| best_low(game, &x.hands, board))
+        .collect();
+    let max_high = *highs.iter().max().unwrap();
+    let min_low = lows.iter().flatten().min().copied();
+    let high_pot = if min_low.is_some() { 0.5 } else { 1.0 };
+    let high_winners = highs.iter().filter(|x| **x == max_high).count() as f64;
+    let low_winners = lows.iter().filter(|x| **x == min_low).count() as f64;
+    highs
+        .iter()
+        .zip(&lows)
+        .map(|(high, low)| {
+            let high_share = if *high == max_high {
+                high_pot / high_winners
+            } else {
+                0.0
+            };
+            let low_share = if min_low.is_some() && *low == min_low {
+                0.5 / low_winners
+            } else {
+                0.0
+            };
+            (high_share, low_share)
+        })
+        .collect()
+}
+
+// 按每局的份额统计分池结果，七张梅花高低也用这里
+#[derive(Default)]
+pub(crate) struct HiLoTally {
+    share: Vec<f64>,
+    scoop: Vec<u64>,
+    high_only: Vec<u64>,
+    low_only: Vec<u64>,
+    quartered: Vec<u64>,
+    low: u64,
+    pub(crate) total: u64,
+}
+
+impl HiLoTally {
+    pub(crate) fn new(players: usize) -> HiLoTally {
+        HiLoTally {
+            share: vec![0.0; players],
+            scoop: vec![0; players],
+            high_only: vec![0; players],
+            low_only: vec![0; players],
+            quartered: vec![0; players],
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn add(&mut self, shares: &[(f64, f64)]) {
+        if shares.iter().any(|x| x.1 > 0.0) {
+            self.low += 1;
+        }
+        for (i, (high, low)) in shares.iter().enumerate() {
+            let share = high + low;
+            self.share[i] += share;
+            if share == 1.0 {
+                self.scoop[i] += 1;
+            } else if *low == 0.0 && *high > 0.0 {
+                self.high_only[i] += 1;
+            } else if *high == 0.0 && *low > 0.0 {
+                self.low_only[i] += 1;
+            }
+            if share == 0.25 {
+                self.quartered[i] += 1;
+            }
+        }
+        self.total += 1;
+    }
+
+    fn rate(&self, x: f64) -> u64 {
+        (x * 10000.0 / self.total as f64).round() as u64
+    }
+
+    pub(crate) fn low_rate(&self) -> u64 {
+        self.rate(self.low as f64)
+    }
+
+    pub(crate) fn clients_rate<'a>(
+        &self,
+        uids: impl Iterator<Item = &'a String>,
+    ) -> Vec<HiLoClientRate> {
+        uids.enumerate()
+            .map(|(i, uid)| HiLoClientRate {
+                uid: uid.clone(),
+                rate: self.rate(self.share[i]),
+                scoop: self.rate(self.scoop[i] as f64),
+                high_only: self.rate(self.high_only[i] as f64),
+                low_only: self.rate(self.low_only[i] as f64),
+                quartered: self.rate(self.quartered[i] as f64),
+            })
+            .collect()
+    }
+}
+
+fn hi_lo_valid(
+    game: HiLoGame,
+    clients: &[HiLoUserCards],
+    deal_cards: &[Card],
+    dead_cards: &[Card],
+    streets: &[usize],
+) -> Result<Vec<Card>, String> {
+    if !streets.contains(&deal_cards.len()) {
+        return Err(format!("deal cards should be one of {:?}", streets));
+    }
+    if clients.len() < 2 || clients.iter().any(|x| x.hands.len() != game.hole_cards()) {
+        return Err(format!(
+            "clients should be at least 2 and each has {} cards",
+            game.hole_cards()
+        ));
+    }
+    let mut known: CardSet = deal_cards.iter().copied().collect();
+    known.extend(dead_cards.iter().copied());
+    let duplicated = clients
+        .iter()
+        .flat_map(|x| &x.hands)
+        .any(|x| !known.insert(*x));
+    if duplicated || DECK_SIZE - known.len() < MAX_BOARD - deal_cards.len() {
+        return Err("req has duplicates or not enough cards".to_string());
+    }
+    Ok(known.complement().iter().collect())
+}
+
+impl Evaluator {
+    pub fn hi_lo_rating(&self, req: HiLoRatingReq) -> HiLoRatingRsp {
+        let mut rsp = HiLoRatingRsp {
+            code: 0,
+            clients_rate: vec![],
+            low_rate: 0,
+            samples: 0,
+            seed: 0,
+            msg: "".to_string(),
+        };
+        let deal_cards = req.deal_cards.cards();
+        let valid = hi_lo_valid(
+            req.game,
+            &req.clients,
+            deal_cards,
+            &req.dead_cards,
+            &[0, 3, 4, 5],
+        );
+        let alive_cards = match valid {
+            Ok(alive_cards) => alive_cards,
+            Err(msg) => {
+                rsp.code = error_model::ERROR_INVALID;
+                rsp.msg = msg;
+                return rsp;
+            }
+        };
+        let remain_card = MAX_BOARD - deal_cards.len();
+        let mut tally = HiLoTally::new(req.clients.len());
+        let mut board = deal_cards.to_vec();
+        if remain_card <= 2 {
+            // 剩余不超过2张时穷举
+            for_each_runout(&alive_cards, remain_card, &mut |runout| {
+                board.truncate(deal_cards.len());
+                board.extend(runout);
+                tally.add(&split_pot(req.game, &req.clients, &board));
+            });
+        } else {
+            let seed = req.seed.unwrap_or_else(|| thread_rng().gen());
+            log_info_display("hi lo rating seed", &seed);
+            rsp.seed = seed;
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let mut deck = alive_cards;
+            for _ in 0..HI_LO_SAMPLES {
+                board.truncate(deal_cards.len());
+                board.extend(draw_cards(&mut rng, &mut deck, remain_card));
+                tally.add(&split_pot(req.game, &req.clients, &board));
+            }
+        }
+        rsp.clients_rate = tally.clients_rate(req.clients.iter().map(|x| &x.uid));
+        rsp.low_rate = tally.low_rate();
+        rsp.samples = tally.total;
+        return rsp;
+    }
+
+    // 只看下一张牌
+    pub fn hi_lo_outs(&self, req: HiLoOutsReq) -> HiLoOutsRsp {
+        let mut rsp = HiLoOutsRsp {
+            code: 0,
+            outs: vec![],
+            msg: "".to_string(),
+        };
+        let deal_cards = req.deal_cards.cards();
+        let valid = hi_lo_valid(req.game, &req.clients, deal_cards, &req.dead_cards, &[3, 4]);
+        let alive_cards = match valid {
+            Ok(alive_cards) => alive_cards,
+            Err(msg) => {
+                rsp.code = error_model::ERROR_INVALID;
+                rsp.msg = msg;
+                return rsp;
+            }
+        };
+        for client in &req.clients {
+            rsp.outs.push(HiLoOuts {
+                uid: client.uid.clone(),
+                high: vec![],
+                low: vec![],
+                scoop: vec![],
+            });
+        }
+        let mut board = deal_cards.to_vec();
+        for card in alive_cards {
+            board.truncate(deal_cards.len());
+            board.push(card);
+            let shares = split_pot(req.game, &req.clients, &board);
+            for (outs, (high, low)) in rsp.outs.iter_mut().zip(shares) {
+                let card = req.notation.format(card);
+                if high > 0.0 {
+                    outs.high.push(card.clone());
+                }
+                if low > 0.0 {
+                    outs.low.push(card.clone());
+                }
+                if high + low == 1.0 {
+                    outs.scoop.push(card);
+                }
+            }
+        }
+        return rsp;
+    }
+}
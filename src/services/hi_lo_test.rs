@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::card::{parse_cards, CardNotation};
+    use crate::models::error_model;
+    use crate::models::model::{
+        HiLoGame, HiLoOutsReq, HiLoRatingReq, HiLoRatingRsp, HiLoUserCards,
+    };
+    use crate::services::evaluator::Evaluator;
+    use crate::services::hi_lo::{best_low, split_pot};
+
+    fn clients(hands: &[&str]) -> Vec<HiLoUserCards> {
+        hands
+            .iter()
+            .enumerate()
+            .map(|(i, x)| HiLoUserCards {
+                hands: parse_cards(x).unwrap(),
+                uid: i.to_string(),
+            })
+            .collect()
+    }
+
+    fn rating(game: HiLoGame, hands: &[&str], board: &str) -> HiLoRatingRsp {
+        let req = HiLoRatingReq {
+            game,
+            clients: clients(hands),
+            deal_cards: board.parse().unwrap(),
+            dead_cards: vec![],
+            seed: Some(1),
+        };
+        Evaluator {}.hi_lo_rating(req)
+    }
+
+    #[test]
+    fn test_best_low() {
+        let board = parse_cards("3c4d5sKc8h").unwrap();
+        // 奥马哈必须用2张手牌，A2加345是最好的低牌
+        let wheel = best_low(
+            HiLoGame::OmahaHiLo,
+            &parse_cards("As2sKhKd").unwrap(),
+            &board,
+        );
+        let seven = best_low(
+            HiLoGame::OmahaHiLo,
+            &parse_cards("6h7h2d9d").unwrap(),
+            &board,
+        );
+        assert_eq!(wheel, Some(0b11111));
+        assert!(seven.unwrap() > wheel.unwrap());
+        // 只有一张低牌的手牌不能成低牌
+        assert_eq!(
+            best_low(
+                HiLoGame::OmahaHiLo,
+                &parse_cards("AsKhQdJd").unwrap(),
+                &board
+            ),
+            None
+        );
+        // 德州可以只用公共牌
+        let board = parse_cards("Ac2d3s4h5c").unwrap();
+        assert_eq!(
+            best_low(HiLoGame::HoldemHiLo, &parse_cards("KsKh").unwrap(), &board),
+            Some(0b11111)
+        );
+    }
+
+    #[test]
+    fn test_split_and_quarter() {
+        let board = parse_cards("5c6d7sKc9h").unwrap();
+        // 0号A2加567赢低牌，1号34加567成顺子赢高牌
+        let shares = split_pot(
+            HiLoGame::OmahaHiLo,
+            &clients(&["As2sKhKd", "3h4hQcQd"]),
+            &board,
+        );
+        assert_eq!(shares, vec![(0.0, 0.5), (0.5, 0.0)]);
+
+        let rsp = rating(HiLoGame::OmahaHiLo, &["As2sKhKd", "3h4hQcQd"], "5c6d7sKc9h");
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.samples, 1);
+        assert_eq!(rsp.low_rate, 10000);
+        assert_eq!(rsp.clients_rate[0].rate, 5000);
+        assert_eq!(rsp.clients_rate[0].low_only, 10000);
+        assert_eq!(rsp.clients_rate[1].high_only, 10000);
+
+        // 低牌都是A2345平分，0号67加345成7顶顺子独得高牌，1号只得到四分之一
+        let rsp = rating(HiLoGame::OmahaHiLo, &["As2s6h7h", "Ad2dQcQh"], "3c4d5sKc8h");
+        assert_eq!(rsp.clients_rate[0].rate, 7500);
+        assert_eq!(rsp.clients_rate[1].rate, 2500);
+        assert_eq!(rsp.clients_rate[1].quartered, 10000);
+        assert_eq!(rsp.clients_rate[1].scoop, 0);
+    }
+
+    #[test]
+    fn test_no_low_scoop() {
+        // 公共牌只有一张低牌，没有低牌时高牌独得底池
+        let rsp = rating(HiLoGame::OmahaHiLo, &["AsAh3d4d", "KhKd5c6c"], "KcQdJs9h2c");
+        assert_eq!(rsp.low_rate, 0);
+        assert_eq!(rsp.clients_rate[1].rate, 10000);
+        assert_eq!(rsp.clients_rate[1].scoop, 10000);
+        assert_eq!(rsp.clients_rate[0].rate, 0);
+
+        // 德州高低牌，A2只赢低牌
+        let rsp = rating(HiLoGame::HoldemHiLo, &["As2h", "KsKh"], "3c4d8sJcQd");
+        assert_eq!(rsp.clients_rate[0].low_only, 10000);
+        assert_eq!(rsp.clients_rate[1].high_only, 10000);
+    }
+
+    #[test]
+    fn test_turn_enumerate() {
+        let rsp = rating(HiLoGame::OmahaHiLo, &["As2sKhKd", "3h4hQcQd"], "5c6d7sKc");
+        assert_eq!(rsp.samples, 40);
+        let total: u64 = rsp.clients_rate.iter().map(|x| x.rate).sum();
+        assert!((9999..=10001).contains(&total));
+    }
+
+    #[test]
+    fn test_outs() {
+        let req = HiLoOutsReq {
+            game: HiLoGame::OmahaHiLo,
+            clients: clients(&["AsAh3d4d", "KhKd5c6c"]),
+            deal_cards: "KcQdJs9h".parse().unwrap(),
+            dead_cards: vec![],
+            notation: CardNotation::Short,
+        };
+        let rsp = Evaluator {}.hi_lo_outs(req);
+        assert_eq!(rsp.code, 0);
+        // 河牌不可能成低牌，0号只有Ac和Ad成三条A，其余的牌1号的三条K独得底池
+        assert!(rsp.outs.iter().all(|x| x.low.is_empty()));
+        assert_eq!(rsp.outs[0].scoop.len(), 2);
+        assert_eq!(rsp.outs[1].scoop.len(), 38);
+        assert_eq!(rsp.outs[1].high, rsp.outs[1].scoop);
+
+        let rsp = rating(HiLoGame::OmahaHiLo, &["AsAh", "KhKd"], "KcQdJs");
+        assert_eq!(rsp.code, error_model::ERROR_INVALID);
+        let rsp = rating(HiLoGame::OmahaHiLo, &["AsAh3d4d"], "KcQdJs");
+        assert_eq!(rsp.code, error_model::ERROR_INVALID);
+    }
+}
@@ -0,0 +1,374 @@
+use std::collections::BTreeMap;
+
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::models::error_model;
+use crate::models::model::{
+    ChopRsp, ChopShare, IcmCallReq, IcmCallRsp, IcmDecisionEv, IcmEquity, IcmHand, IcmMethod,
+    IcmPlayer, IcmReq, IcmRsp, Opponent,
+};
+use crate::services::evaluator::Evaluator;
+use crate::utils::log::log_info_display;
+
+// 玩家人数上限
+const MAX_PLAYERS: usize = 10000;
+
+// 精确计算最多的状态数(已决定名次的玩家集合)，超过时自动改用随机法
+const MAX_EXACT_STATES: u64 = 1 << 18;
+
+// 精确计算用位掩码表示玩家集合
+const MAX_EXACT_PLAYERS: usize = 32;
+
+const DEFAULT_SAMPLES: u64 = 100000;
+
+// 随机法的采样次数乘以人数的上限，人数多时默认采样次数相应减少，
+// 达到上限时约需两百毫秒，保证在接口2秒的超时内返回
+const MAX_SAMPLED_PLAYERS: u64 = 10000000;
+
+impl Evaluator {
+    pub fn icm(&self, req: IcmReq) -> IcmRsp {
+        let mut rsp = IcmRsp {
+            code: 0,
+            players: vec![],
+            method: req.method,
+            samples: 0,
+            seed: None,
+            msg: "".to_string(),
+        };
+        if let Err(msg) = icm_valid(&req) {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = msg;
+            return rsp;
+        }
+        let stacks: Vec<u64> = req.players.iter().map(|x| x.stack).collect();
+        // 多于人数的奖金不会发出
+        let payouts = &req.payouts[..req.payouts.len().min(stacks.len())];
+        let states = exact_states(stacks.len(), payouts.len());
+        let exact = match req.method {
+            IcmMethod::Auto => states <= MAX_EXACT_STATES,
+            IcmMethod::Exact => true,
+            IcmMethod::MonteCarlo => false,
+        };
+        let equities = if exact {
+            if states > MAX_EXACT_STATES {
+                rsp.code = error_model::ERROR_INVALID;
+                rsp.msg = "too many players and payouts for exact method".to_string();
+                return rsp;
+            }
+            rsp.method = IcmMethod::Exact;
+            malmuth_harville(&stacks, payouts)
+        } else {
+            let max_samples = MAX_SAMPLED_PLAYERS / stacks.len() as u64;
+            let samples = req.samples.unwrap_or(DEFAULT_SAMPLES.min(max_samples));
+            if !(1..=max_samples).contains(&samples) {
+                rsp.code = error_model::ERROR_INVALID;
+                rsp.msg = format!("samples should be between 1 and {}", max_samples);
+                return rsp;
+            }
+            let seed = req.seed.unwrap_or_else(|| thread_rng().gen());
+            log_info_display("icm seed", &seed);
+            rsp.method = IcmMethod::MonteCarlo;
+            rsp.samples = samples;
+            rsp.seed = Some(seed);
+            monte_carlo(&stacks, payouts, samples, seed)
+        };
+        let pool: u64 = payouts.iter().sum();
+        for (player, equity) in req.players.iter().zip(equities) {
+            rsp.players.push(IcmEquity {
+                uid: player.uid.clone(),
+                stack: player.stack,
+                equity,
+                share: if pool > 0 {
+                    (equity / pool as f64 * 10000.0).round() as u64
+                } else {
+                    0
+                },
+            });
+        }
+        return rsp;
+    }
+
+    // 按ICM和按筹码两种方式分奖金，金额取整后总和不变
+    pub fn icm_chop(&self, req: IcmReq) -> ChopRsp {
+        let mut rsp = ChopRsp {
+            code: 0,
+            players: vec![],
+            method: req.method,
+            msg: "".to_string(),
+        };
+        let stacks: Vec<u64> = req.players.iter().map(|x| x.stack).collect();
+        let mut payouts = req.payouts.clone();
+        payouts.resize(stacks.len(), 0);
+        let icm = self.icm(req);
+        if icm.code != 0 {
+            rsp.code = icm.code;
+            rsp.msg = icm.msg;
+            return rsp;
+        }
+        let pool: u64 = payouts.iter().sum();
+        let icm_amounts = round_amounts(
+            &icm.players.iter().map(|x| x.equity).collect::<Vec<f64>>(),
+            pool,
+        );
+        let chip_amounts = round_amounts(&chip_chop(&stacks, &payouts), pool);
+        for (i, player) in icm.players.iter().enumerate() {
+            rsp.players.push(ChopShare {
+                uid: player.uid.clone(),
+                stack: player.stack,
+                icm: icm_amounts[i],
+                chip_chop: chip_amounts[i],
+            });
+        }
+        rsp.method = icm.method;
+        return rsp;
+    }
+}
+
+impl Evaluator {
+    // 面对全下时跟注方跟注和弃牌后所有玩家的奖金权益，平局按一半胜一半负近似
+    pub fn icm_call(&self, req: IcmCallReq) -> IcmCallRsp {
+        let mut rsp = IcmCallRsp {
+            code: 0,
+            equity: 0,
+            required_equity: 0,
+            chip_required_equity: 0,
+            should_call: false,
+            players: vec![],
+            msg: "".to_string(),
+        };
+        if let Err(msg) = self.icm_call_ev(&req, &mut rsp) {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = msg;
+        }
+        return rsp;
+    }
+
+    fn icm_call_ev(&self, req: &IcmCallReq, rsp: &mut IcmCallRsp) -> Result<(), String> {
+        let index = |uid: &str| req.players.iter().position(|x| x.uid == uid);
+        let (Some(shover), Some(caller)) = (index(&req.shover.uid), index(&req.caller.uid)) else {
+            return Err("shover or caller is not in players".to_string());
+        };
+        if shover == caller || req.players.iter().any(|x| x.stack == 0) {
+            return Err("shover and caller should be different and stacks positive".to_string());
+        }
+        // 用手牌已知的一方计算胜率
+        let opponent = |hand: &IcmHand| Opponent {
+            hands: hand.hands,
+            range: hand.range.clone(),
+        };
+        let equity = match (req.caller.hands, req.shover.hands) {
+            (Some(hands), _) => {
+                let opponents = [opponent(&req.shover)];
+                let (equity, _, _) = self.hero_equity(
+                    hands,
+                    &opponents,
+                    &req.deal_cards,
+                    &req.dead_cards,
+                    req.seed,
+                )?;
+                equity
+            }
+            (None, Some(hands)) => {
+                let opponents = [opponent(&req.caller)];
+                let (equity, _, _) = self.hero_equity(
+                    hands,
+                    &opponents,
+                    &req.deal_cards,
+                    &req.dead_cards,
+                    req.seed,
+                )?;
+                10000 - equity
+            }
+            (None, None) => return Err("shover or caller should have known hands".to_string()),
+        };
+        let stacks: Vec<u64> = req.players.iter().map(|x| x.stack).collect();
+        let effective = stacks[shover].min(stacks[caller]);
+        let mut fold = stacks.clone();
+        fold[shover] += req.dead_money;
+        let mut win = stacks.clone();
+        win[caller] += effective + req.dead_money;
+        win[shover] -= effective;
+        let mut lose = stacks.clone();
+        lose[caller] -= effective;
+        lose[shover] += effective + req.dead_money;
+        let fold = self.icm_with_busted(&req.players, &fold, &req.payouts, req.seed)?;
+        let win = self.icm_with_busted(&req.players, &win, &req.payouts, req.seed)?;
+        let lose = self.icm_with_busted(&req.players, &lose, &req.payouts, req.seed)?;
+        let p = equity as f64 / 10000.0;
+        for (i, player) in req.players.iter().enumerate() {
+            rsp.players.push(IcmDecisionEv {
+                uid: player.uid.clone(),
+                fold: fold[i],
+                call: p * win[i] + (1.0 - p) * lose[i],
+            });
+        }
+        let to_rate = |x: f64| (x.clamp(0.0, 1.0) * 10000.0).round() as u64;
+        rsp.equity = equity;
+        rsp.required_equity = to_rate((fold[caller] - lose[caller]) / (win[caller] - lose[caller]));
+        rsp.chip_required_equity =
+            to_rate(effective as f64 / (2 * effective + req.dead_money) as f64);
+        rsp.should_call = rsp.players[caller].call > rsp.players[caller].fold;
+        Ok(())
+    }
+
+    // 筹码为0的玩家已出局，平分还剩人数之后的名次奖金，其余玩家按ICM计算
+    fn icm_with_busted(
+        &self,
+        players: &[IcmPlayer],
+        stacks: &[u64],
+        payouts: &[u64],
+        seed: Option<u64>,
+    ) -> Result<Vec<f64>, String> {
+        let alive: Vec<usize> = (0..stacks.len()).filter(|i| stacks[*i] > 0).collect();
+        let payout = |place: usize| payouts.get(place).copied().unwrap_or(0) as f64;
+        let busted = stacks.len() - alive.len();
+        let busted_equity =
+            (alive.len()..stacks.len()).map(payout).sum::<f64>() / busted.max(1) as f64;
+        let mut equities = vec![busted_equity; stacks.len()];
+        if alive.len() == 1 {
+            equities[alive[0]] = payout(0);
+            return Ok(equities);
+        }
+        let rsp = self.icm(IcmReq {
+            players: alive
+                .iter()
+                .map(|i| IcmPlayer {
+                    uid: players[*i].uid.clone(),
+                    stack: stacks[*i],
+                })
+                .collect(),
+            payouts: payouts.to_vec(),
+            method: IcmMethod::Auto,
+            samples: None,
+            seed,
+        });
+        if rsp.code != 0 {
+            return Err(rsp.msg);
+        }
+        for (i, equity) in alive.iter().zip(rsp.players) {
+            equities[*i] = equity.equity;
+        }
+        Ok(equities)
+    }
+}
+
+fn icm_valid(req: &IcmReq) -> Result<(), String> {
+    if req.players.len() < 2 || req.players.len() > MAX_PLAYERS {
+        return Err(format!("players should be between 2 and {}", MAX_PLAYERS));
+    }
+    if req.players.iter().any(|x| x.stack == 0) || req.payouts.is_empty() {
+        return Err("stack should be positive and payouts should not be empty".to_string());
+    }
+    let total = req
+        .players
+        .iter()
+        .try_fold(0u64, |acc, x| acc.checked_add(x.stack));
+    let pool = req
+        .payouts
+        .iter()
+        .try_fold(0u64, |acc, x| acc.checked_add(*x));
+    if total.is_none() || pool.is_none() {
+        return Err("stacks or payouts overflow".to_string());
+    }
+    Ok(())
+}
+
+// 前k名已决定的玩家集合数量，即k从0到名次数-1的组合数之和
+fn exact_states(players: usize, places: usize) -> u64 {
+    if players > MAX_EXACT_PLAYERS {
+        return u64::MAX;
+    }
+    let mut states = 0u64;
+    let mut binomial = 1u64;
+    for k in 0..places {
+        states = states.saturating_add(binomial);
+        binomial = binomial.saturating_mul((players - k) as u64) / (k as u64 + 1);
+    }
+    states
+}
+
+// 每个名次按剩余筹码比例决定，按已决定名次的玩家集合做动态规划
+fn malmuth_harville(stacks: &[u64], payouts: &[u64]) -> Vec<f64> {
+    let n = stacks.len();
+    let total: f64 = stacks.iter().map(|x| *x as f64).sum();
+    let mut equities = vec![0.0; n];
+    // 按人数分层，prob[mask]为前popcount(mask)名恰好是mask中玩家的概率
+    let mut layer: Vec<(u32, f64, f64)> = vec![(0, 1.0, total)];
+    for payout in payouts {
+        let mut next: BTreeMap<u32, (f64, f64)> = BTreeMap::new();
+        for (mask, prob, remain) in &layer {
+            for (i, stack) in stacks.iter().enumerate() {
+                if mask & (1 << i) != 0 {
+                    continue;
+                }
+                let p = prob * *stack as f64 / remain;
+                equities[i] += p * *payout as f64;
+                let entry = next
+                    .entry(mask | (1 << i))
+                    .or_insert((0.0, remain - *stack as f64));
+                entry.0 += p;
+            }
+        }
+        layer = next
+            .into_iter()
+            .map(|(mask, (prob, remain))| (mask, prob, remain))
+            .collect();
+    }
+    equities
+}
+
+// 每个玩家取key = -ln(u)/stack，key从小到大即为名次，与Malmuth–Harville的分布相同
+fn monte_carlo(stacks: &[u64], payouts: &[u64], samples: u64, seed: u64) -> Vec<f64> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut equities = vec![0.0; stacks.len()];
+    let mut keys: Vec<(f64, usize)> = vec![(0.0, 0); stacks.len()];
+    let places = payouts.len();
+    for _ in 0..samples {
+        for (i, stack) in stacks.iter().enumerate() {
+            let u: f64 = rng.gen_range(f64::MIN_POSITIVE..1.0);
+            keys[i] = (-u.ln() / *stack as f64, i);
+        }
+        // 只需要前places名
+        if places < keys.len() {
+            keys.select_nth_unstable_by(places - 1, |a, b| a.0.total_cmp(&b.0));
+        }
+        keys[..places].sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+        for (place, (_, i)) in keys[..places].iter().enumerate() {
+            equities[*i] += payouts[place] as f64;
+        }
+    }
+    equities.iter().map(|x| x / samples as f64).collect()
+}
+
+// payouts已补齐到人数，每人先得最低的奖金
+fn chip_chop(stacks: &[u64], payouts: &[u64]) -> Vec<f64> {
+    let min = *payouts.iter().min().unwrap() as f64;
+    let pool: f64 = payouts.iter().map(|x| *x as f64).sum();
+    let total: f64 = stacks.iter().map(|x| *x as f64).sum();
+    let rest = pool - min * stacks.len() as f64;
+    stacks
+        .iter()
+        .map(|x| min + rest * *x as f64 / total)
+        .collect()
+}
+
+// 最大余数法取整，保证总和等于total
+fn round_amounts(values: &[f64], total: u64) -> Vec<u64> {
+    let mut amounts: Vec<u64> = values.iter().map(|x| x.max(0.0).floor() as u64).collect();
+    let assigned: u64 = amounts.iter().sum();
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|a, b| {
+        let fa = values[*a] - values[*a].floor();
+        let fb = values[*b] - values[*b].floor();
+        fb.total_cmp(&fa).then(a.cmp(b))
+    });
+    for i in order
+        .into_iter()
+        .cycle()
+        .take(total.saturating_sub(assigned) as usize)
+    {
+        amounts[i] += 1;
+    }
+    amounts
+}
@@ -0,0 +1,161 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::card::parse_cards;
+    use crate::models::error_model;
+    use crate::models::model::{IcmCallReq, IcmHand, IcmMethod, IcmPlayer, IcmReq};
+    use crate::services::evaluator::Evaluator;
+
+    fn req(stacks: &[u64], payouts: &[u64], method: IcmMethod) -> IcmReq {
+        IcmReq {
+            players: stacks
+                .iter()
+                .enumerate()
+                .map(|(i, stack)| IcmPlayer {
+                    uid: (i + 1).to_string(),
+                    stack: *stack,
+                })
+                .collect(),
+            payouts: payouts.to_vec(),
+            method,
+            samples: None,
+            seed: Some(7),
+        }
+    }
+
+    fn equities(req: IcmReq) -> Vec<f64> {
+        let rsp = Evaluator {}.icm(req);
+        assert_eq!(rsp.code, 0);
+        rsp.players.iter().map(|x| x.equity).collect()
+    }
+
+    #[test]
+    fn test_icm_exact() {
+        // 与穷举全部名次顺序的结果一致
+        let expected = [38.392857, 32.75, 28.857143];
+        let actual = equities(req(&[5000, 3000, 2000], &[50, 30, 20], IcmMethod::Auto));
+        for (a, b) in actual.iter().zip(expected) {
+            assert!((a - b).abs() < 1e-5, "{} {}", a, b);
+        }
+        // 只有两个名次有奖金
+        let expected = [39.949495, 28.969697, 20.424242, 10.656566];
+        let actual = equities(req(&[5000, 3000, 2000, 1000], &[60, 40], IcmMethod::Exact));
+        for (a, b) in actual.iter().zip(expected) {
+            assert!((a - b).abs() < 1e-5, "{} {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_icm_monte_carlo() {
+        let stacks = [5000, 3000, 2000, 1000];
+        let exact = equities(req(&stacks, &[60, 40], IcmMethod::Exact));
+        let rsp = Evaluator {}.icm(req(&stacks, &[60, 40], IcmMethod::MonteCarlo));
+        assert_eq!(rsp.method, IcmMethod::MonteCarlo);
+        assert_eq!(rsp.samples, 100000);
+        for (a, b) in rsp.players.iter().zip(exact) {
+            assert!((a.equity - b).abs() < 0.5, "{} {}", a.equity, b);
+        }
+        // 人数多时自动改用随机法
+        let stacks = vec![1000; 40];
+        let payouts: Vec<u64> = (1..=10).rev().collect();
+        let rsp = Evaluator {}.icm(req(&stacks, &payouts, IcmMethod::Auto));
+        assert_eq!(rsp.method, IcmMethod::MonteCarlo);
+        let rsp = Evaluator {}.icm(req(&stacks, &payouts, IcmMethod::Exact));
+        assert_eq!(rsp.code, error_model::ERROR_INVALID);
+    }
+
+    #[test]
+    fn test_icm_chop() {
+        let rsp = Evaluator {}.icm_chop(req(&[5000, 3000, 2000], &[50, 30, 20], IcmMethod::Auto));
+        assert_eq!(rsp.code, 0);
+        let icm: Vec<u64> = rsp.players.iter().map(|x| x.icm).collect();
+        let chip_chop: Vec<u64> = rsp.players.iter().map(|x| x.chip_chop).collect();
+        assert_eq!(icm, vec![38, 33, 29]);
+        // 每人先得20，其余40按筹码比例分
+        assert_eq!(chip_chop, vec![40, 32, 28]);
+
+        let rsp = Evaluator {}.icm_chop(req(&[5000, 0], &[50, 30], IcmMethod::Auto));
+        assert_eq!(rsp.code, error_model::ERROR_INVALID);
+    }
+
+    fn hand(uid: &str, hands: Option<&str>, range: Option<&str>) -> IcmHand {
+        IcmHand {
+            uid: uid.to_string(),
+            hands: hands.map(|x| {
+                let cards = parse_cards(x).unwrap();
+                [cards[0], cards[1]]
+            }),
+            range: range.map(|x| x.to_string()),
+        }
+    }
+
+    fn call_req(shover: IcmHand, caller: IcmHand, board: &str) -> IcmCallReq {
+        IcmCallReq {
+            players: req(&[5000, 3000, 1000, 1000], &[], IcmMethod::Auto).players,
+            payouts: vec![50, 30, 20],
+            shover,
+            caller,
+            dead_money: 0,
+            deal_cards: board.parse().unwrap(),
+            dead_cards: vec![],
+            seed: Some(7),
+        }
+    }
+
+    #[test]
+    fn test_icm_call() {
+        // 河牌AA对KK必胜
+        let req = call_req(
+            hand("1", Some("KsKh"), None),
+            hand("2", Some("AsAh"), None),
+            "2c7d9hJdQc",
+        );
+        let rsp = Evaluator {}.icm_call(req);
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.equity, 10000);
+        assert!(rsp.should_call);
+        let fold: f64 = rsp.players.iter().map(|x| x.fold).sum();
+        let call: f64 = rsp.players.iter().map(|x| x.call).sum();
+        assert!((fold - 100.0).abs() < 1e-6 && (call - 100.0).abs() < 1e-6);
+        // 泡沫时ICM需要的胜率高于筹码需要的胜率
+        assert_eq!(rsp.chip_required_equity, 5000);
+        assert!(rsp.required_equity > 5000);
+
+        // 跟注方为范围，用全下方的手牌计算
+        let req = call_req(
+            hand("1", Some("AsAh"), None),
+            hand("2", None, Some("KK")),
+            "2c7d9hJdQc",
+        );
+        let rsp = Evaluator {}.icm_call(req);
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.equity, 0);
+        assert!(!rsp.should_call);
+        // 输掉后出局得第4名，没有奖金
+        assert_eq!(rsp.players[1].call, 0.0);
+
+        // 大盲面对小盲全下，stack已扣除盲注，dead_money含双方的盲注
+        let mut req = call_req(
+            hand("1", Some("KsKh"), None),
+            hand("2", Some("AsAh"), None),
+            "2c7d9hJdQc",
+        );
+        req.dead_money = 100 + 200;
+        let rsp = Evaluator {}.icm_call(req);
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.chip_required_equity, 4762);
+        let fold: f64 = rsp.players.iter().map(|x| x.fold).sum();
+        assert!((fold - 100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_icm_call_invalid() {
+        let req = call_req(hand("1", None, Some("AA")), hand("2", None, Some("KK")), "");
+        assert_eq!(Evaluator {}.icm_call(req).code, error_model::ERROR_INVALID);
+        let req = call_req(
+            hand("1", Some("AsAh"), None),
+            hand("5", None, Some("KK")),
+            "",
+        );
+        assert_eq!(Evaluator {}.icm_call(req).code, error_model::ERROR_INVALID);
+    }
+}
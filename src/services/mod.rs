@@ -1,2 +1,32 @@
+pub mod allin_ev;
+mod allin_ev_test;
+pub mod board_texture;
+mod board_texture_test;
+pub mod double_board;
+mod double_board_test;
+pub mod draws;
+mod draws_test;
 pub mod evaluator;
 mod evaluator_test;
+pub mod hand_history;
+mod hand_history_test;
+pub mod hand_strength;
+mod hand_strength_test;
+pub mod hi_lo;
+mod hi_lo_test;
+pub mod icm;
+mod icm_test;
+pub mod nut_rank;
+mod nut_rank_test;
+pub mod pot_odds;
+mod pot_odds_test;
+pub mod promotion;
+mod promotion_test;
+pub mod push_fold;
+mod push_fold_test;
+pub mod rabbit_hunt;
+mod rabbit_hunt_test;
+pub mod shuffle;
+mod shuffle_test;
+pub mod stud;
+mod stud_test;
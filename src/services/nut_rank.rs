@@ -0,0 +1,60 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+use crate::models::card::{CardSet, HandCategory};
+use crate::models::error_model;
+use crate::models::model::{NutRankReq, NutRankRsp, UserCards};
+use crate::services::evaluator::Evaluator;
+
+impl Evaluator {
+    // 穷举去掉已知牌和dead cards后的全部两张牌组合，与这手牌比较
+    pub fn nut_rank(&self, req: NutRankReq) -> NutRankRsp {
+        let mut rsp = NutRankRsp {
+            code: 0,
+            category: HandCategory::HighCard,
+            value: 0,
+            rank: 0,
+            beat: 0,
+            tie: 0,
+            lose: 0,
+            combos: 0,
+            msg: "".to_string(),
+        };
+        let mut known = req.deal_cards.card_set();
+        known.extend(req.dead_cards.iter().copied());
+        let duplicated = req.hands.iter().any(|x| !known.insert(*x));
+        if duplicated || req.deal_cards.len() < 3 {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = "req has duplicates or deal cards less than 3".to_string();
+            return rsp;
+        }
+        let client = UserCards {
+            hands: req.hands,
+            uid: "".to_string(),
+        };
+        let (board, alive_cards) =
+            self.get_board_and_alive_cards(&req.deal_cards, &req.dead_cards, &[client]);
+        let value = (board + CardSet::from_iter(req.hands).hand()).evaluate();
+        // 比这手牌大的不同牌力
+        let mut stronger = BTreeSet::new();
+        for i in 0..alive_cards.len() {
+            for j in i + 1..alive_cards.len() {
+                let hand = CardSet::from_iter([alive_cards[i], alive_cards[j]]).hand();
+                let theirs = (board + hand).evaluate();
+                match theirs.cmp(&value) {
+                    Ordering::Greater => {
+                        rsp.beat += 1;
+                        stronger.insert(theirs);
+                    }
+                    Ordering::Equal => rsp.tie += 1,
+                    Ordering::Less => rsp.lose += 1,
+                }
+            }
+        }
+        rsp.category = HandCategory::of(value);
+        rsp.value = value;
+        rsp.rank = stronger.len() as u32 + 1;
+        rsp.combos = rsp.beat + rsp.tie + rsp.lose;
+        return rsp;
+    }
+}
@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::card::{parse_cards, HandCategory};
+    use crate::models::error_model;
+    use crate::models::model::NutRankReq;
+    use crate::services::evaluator::Evaluator;
+
+    fn nut_rank(hands: &str, board: &str, dead: &str) -> (u32, u32, u32, u32) {
+        let cards = parse_cards(hands).unwrap();
+        let req = NutRankReq {
+            hands: [cards[0], cards[1]],
+            deal_cards: board.parse().unwrap(),
+            dead_cards: parse_cards(dead).unwrap(),
+        };
+        let rsp = Evaluator {}.nut_rank(req);
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.combos, rsp.beat + rsp.tie + rsp.lose);
+        (rsp.rank, rsp.beat, rsp.tie, rsp.lose)
+    }
+
+    #[test]
+    fn test_nut_rank() {
+        // 顶三条是坚果，剩下47张牌共1081种组合
+        assert_eq!(nut_rank("8c8s", "8h7h2c", ""), (1, 0, 0, 1081));
+        // 中三条输给3种88
+        assert_eq!(nut_rank("7c7s", "8h7h2c", ""), (2, 3, 0, 1078));
+        // 底三条是第三坚果
+        assert_eq!(nut_rank("2s2d", "8h7h2c", "").0, 3);
+        // 8s已知时只剩8c8d
+        assert_eq!(nut_rank("7c7s", "8h7h2c", "8s").1, 1);
+        // 公共牌皇家同花顺时全部平分
+        assert_eq!(nut_rank("2c3c", "AsKsQsJsTs", ""), (1, 0, 990, 0));
+    }
+
+    #[test]
+    fn test_nut_rank_invalid() {
+        let cards = parse_cards("AsKs").unwrap();
+        let req = NutRankReq {
+            hands: [cards[0], cards[1]],
+            deal_cards: "AsKhQh".parse().unwrap(),
+            dead_cards: vec![],
+        };
+        let rsp = Evaluator {}.nut_rank(req);
+        assert_eq!(rsp.code, error_model::ERROR_INVALID);
+        assert_eq!(rsp.category, HandCategory::HighCard);
+    }
+}
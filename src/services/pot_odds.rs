@@ -0,0 +1,290 @@
+use holdem_hand_evaluator::Hand;
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::models::card::{Board, Card, CardSet, DECK_SIZE, MAX_BOARD};
+use crate::models::error_model;
+use crate::models::model::{CalculateRatingReq, Opponent, PotOddsReq, PotOddsRsp, UserCards};
+use crate::models::range::Range;
+use crate::services::evaluator::{deck_valid, Evaluator};
+use crate::utils::log::log_info_display;
+
+// 有对手为范围时的采样次数
+const RANGE_SAMPLES: u64 = 50000;
+
+// 为范围内的对手整组抽取不冲突组合的尝试次数，超过后放弃这次采样
+const MAX_ATTEMPTS: usize = 100;
+
+enum OpponentCards {
+    Known([Card; 2]),
+    Range(Range),
+}
+
+impl Evaluator {
+    pub fn pot_odds(&self, req: PotOddsReq) -> PotOddsRsp {
+        let mut rsp = PotOddsRsp {
+            code: 0,
+            required_equity: 0,
+            equity: 0,
+            call_ev: 0.0,
+            bluff_fold_frequency: 0,
+            samples: 0,
+            seed: 0,
+            msg: "".to_string(),
+        };
+        if req.to_call == 0 {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = "to_call is 0".to_string();
+            return rsp;
+        }
+        let (equity, samples, seed) = match self.hero_equity(
+            req.hands,
+            &req.opponents,
+            &req.deal_cards,
+            &req.dead_cards,
+            req.seed,
+        ) {
+            Ok(result) => result,
+            Err(msg) => {
+                rsp.code = error_model::ERROR_INVALID;
+                rsp.msg = msg;
+                return rsp;
+            }
+        };
+        rsp.equity = equity;
+        rsp.samples = samples;
+        rsp.seed = seed;
+        let pot = req.pot as f64;
+        let call = req.to_call as f64;
+        let to_rate = |x: f64| (x * 10000.0).round() as u64;
+        // 跟注后底池为下注前的底池加双方的下注
+        rsp.required_equity = to_rate(call / (pot + 2.0 * call));
+        rsp.call_ev = rsp.equity as f64 / 10000.0 * (pot + 2.0 * call) - call;
+        rsp.bluff_fold_frequency = to_rate(call / (pot + call));
+        return rsp;
+    }
+
+    // 手牌对已知手牌或范围的胜率，返回(胜率, 采样次数, 种子)，手牌都已知时与calculate_rating相同
+    pub(crate) fn hero_equity(
+        &self,
+        hands: [Card; 2],
+        opponents: &[Opponent],
+        deal_cards: &Board,
+        dead_cards: &[Card],
+        seed: Option<u64>,
+    ) -> Result<(u64, u64, u64), String> {
+        let opponents = opponents_valid(hands, opponents, deal_cards, dead_cards)?;
+        let seed = seed.unwrap_or_else(|| thread_rng().gen());
+        let ranged = opponents
+            .iter()
+            .any(|x| matches!(x, OpponentCards::Range(_)));
+        if ranged {
+            log_info_display("equity seed", &seed);
+            let (equity, samples) =
+                self.range_equity(hands, &opponents, deal_cards, dead_cards, seed);
+            if samples == 0 {
+                return Err("ranges have no combos without conflicts".to_string());
+            }
+            return Ok((equity, samples, seed));
+        }
+        let mut clients = vec![UserCards {
+            hands,
+            uid: "hero".to_string(),
+        }];
+        for (i, opponent) in opponents.iter().enumerate() {
+            if let OpponentCards::Known(hands) = opponent {
+                clients.push(UserCards {
+                    hands: *hands,
+                    uid: (i + 1).to_string(),
+                });
+            }
+        }
+        let rating = self.rating(CalculateRatingReq {
+            clients,
+            deal_cards: deal_cards.clone(),
+            dead_cards: dead_cards.to_vec(),
+            exact: false,
+            target_std_err: None,
+            time_budget_ms: None,
+            seed: Some(seed),
+            samples: None,
+            second_board: None,
+        });
+        if rating.code != 0 {
+            return Err(rating.msg);
+        }
+        Ok((rating.clients_rate[0].rate, rating.samples, rating.seed))
+    }
+
+    // 每次采样为范围内的对手各抽一个不冲突的组合，再补全公共牌
+    fn range_equity(
+        &self,
+        hands: [Card; 2],
+        opponents: &[OpponentCards],
+        deal_cards: &Board,
+        dead_cards: &[Card],
+        seed: u64,
+    ) -> (u64, u64) {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut known = deal_cards.card_set();
+        known.extend(dead_cards.iter().copied());
+        known.extend(hands);
+        for opponent in opponents {
+            if let OpponentCards::Known(hands) = opponent {
+                known.extend(*hands);
+            }
+        }
+        // 先去掉与已知的牌冲突的组合，再对范围内的对手整组抽取，有冲突时整组重抽，
+        // 这样每组互不冲突的组合被抽中的概率相同
+        let ranges: Vec<Vec<[Card; 2]>> = opponents
+            .iter()
+            .filter_map(|x| match x {
+                OpponentCards::Range(range) => Some(
+                    range
+                        .combos()
+                        .iter()
+                        .filter(|x| !known.contains(x[0]) && !known.contains(x[1]))
+                        .copied()
+                        .collect(),
+                ),
+                OpponentCards::Known(_) => None,
+            })
+            .collect();
+        if ranges.iter().any(|x| x.is_empty()) {
+            return (0, 0);
+        }
+        let board = deal_cards.hand();
+        let hero = CardSet::from_iter(hands).hand();
+        let mut opponent_hands: Vec<_> = opponents
+            .iter()
+            .filter_map(|x| match x {
+                OpponentCards::Known(hands) => Some(CardSet::from_iter(*hands).hand()),
+                OpponentCards::Range(_) => None,
+            })
+            .collect();
+        let known_len = opponent_hands.len();
+        let remain_card = MAX_BOARD - deal_cards.len();
+        let (mut win, mut draw, mut total) = (0u64, 0u64, 0u64);
+        let mut combos = vec![];
+        // deck只分配一次，包含范围内对手可能拿到的牌，补全公共牌时跳过本次被抽中的组合
+        let mut deck: Vec<Card> = known.complement().iter().collect();
+        for _ in 0..RANGE_SAMPLES {
+            let mut picked = None;
+            for _ in 0..MAX_ATTEMPTS {
+                let mut used = known;
+                combos.clear();
+                let conflicted = ranges.iter().any(|range| {
+                    let combo = range[rng.gen_range(0..range.len())];
+                    combos.push(combo);
+                    !used.insert(combo[0]) | !used.insert(combo[1])
+                });
+                if !conflicted {
+                    picked = Some(used);
+                    break;
+                }
+            }
+            // 多次都冲突时放弃这次采样，samples返回实际采样的次数
+            let Some(used) = picked else {
+                continue;
+            };
+            opponent_hands.truncate(known_len);
+            opponent_hands.extend(combos.iter().map(|x| CardSet::from_iter(*x).hand()));
+            let runout = sample_runout(&mut rng, board, &mut deck, used, remain_card);
+            let ours = (hero + runout).evaluate();
+            let best = opponent_hands
+                .iter()
+                .map(|x| (*x + runout).evaluate())
+                .max()
+                .unwrap();
+            if ours > best {
+                win += 1;
+            } else if ours == best {
+                draw += 1;
+            }
+            total += 1;
+        }
+        if total == 0 {
+            return (0, 0);
+        }
+        ((win * 10000 + draw * 5000) / total, total)
+    }
+}
+
+// 部分Fisher–Yates洗牌，抽到used中的牌时换到末尾不再参与本次抽取，
+// 每张可用的牌被抽中的概率相同，deck在多次调用间保持打乱后的状态
+fn sample_runout(
+    rng: &mut ChaCha8Rng,
+    board: Hand,
+    deck: &mut [Card],
+    used: CardSet,
+    remain_card: usize,
+) -> Hand {
+    let mut runout = board;
+    let mut end = deck.len();
+    let mut i = 0;
+    while i < remain_card {
+        let j = rng.gen_range(i..end);
+        deck.swap(i, j);
+        if used.contains(deck[i]) {
+            end -= 1;
+            deck.swap(i, end);
+            continue;
+        }
+        runout = runout.add_card(deck[i].id());
+        i += 1;
+    }
+    runout
+}
+
+fn opponents_valid(
+    hands: [Card; 2],
+    opponents: &[Opponent],
+    deal_cards: &Board,
+    dead_cards: &[Card],
+) -> Result<Vec<OpponentCards>, String> {
+    if opponents.is_empty() {
+        return Err("opponents is empty".to_string());
+    }
+    if !matches!(deal_cards.len(), 0 | 3 | 4 | 5) {
+        return Err("deal cards should be 0, 3, 4 or 5".to_string());
+    }
+    let mut known = deal_cards.card_set();
+    known.extend(dead_cards.iter().copied());
+    let mut duplicated = hands.iter().any(|x| !known.insert(*x));
+    let mut result = vec![];
+    for opponent in opponents {
+        match (&opponent.hands, &opponent.range) {
+            (Some(hands), None) => {
+                duplicated |= hands.iter().any(|x| !known.insert(*x));
+                result.push(OpponentCards::Known(*hands));
+            }
+            (None, Some(range)) => {
+                let range = range
+                    .parse::<Range>()
+                    .map_err(|_| format!("range {} is invalid", range))?;
+                result.push(OpponentCards::Range(range));
+            }
+            _ => return Err("opponent should have either hands or range".to_string()),
+        }
+    }
+    if duplicated {
+        return Err("req has duplicates".to_string());
+    }
+    // 去掉与已知牌冲突的组合
+    for opponent in result.iter_mut() {
+        if let OpponentCards::Range(range) = opponent {
+            *range = range.without(known);
+            if range.is_empty() {
+                return Err("range has no combos left".to_string());
+            }
+        }
+    }
+    // 范围内的对手各占两张后还要能发完公共牌
+    let ranges = result
+        .iter()
+        .filter(|x| matches!(x, OpponentCards::Range(_)))
+        .count();
+    let alive = (DECK_SIZE - known.len()).saturating_sub(2 * ranges);
+    deck_valid(alive, MAX_BOARD - deal_cards.len())?;
+    Ok(result)
+}
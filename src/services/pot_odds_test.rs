@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::card::{parse_cards, Card, CardSet};
+    use crate::models::error_model;
+    use crate::models::model::{Opponent, PotOddsReq, PotOddsRsp};
+    use crate::services::evaluator::Evaluator;
+
+    fn pot_odds(hands: &str, opponent: Opponent, board: &str) -> PotOddsRsp {
+        let cards = parse_cards(hands).unwrap();
+        let req = PotOddsReq {
+            hands: [cards[0], cards[1]],
+            opponents: vec![opponent],
+            deal_cards: board.parse().unwrap(),
+            dead_cards: vec![],
+            pot: 100,
+            to_call: 50,
+            seed: Some(7),
+        };
+        Evaluator {}.pot_odds(req)
+    }
+
+    fn known(hands: &str) -> Opponent {
+        let cards = parse_cards(hands).unwrap();
+        Opponent {
+            hands: Some([cards[0], cards[1]]),
+            range: None,
+        }
+    }
+
+    fn range(range: &str) -> Opponent {
+        Opponent {
+            hands: None,
+            range: Some(range.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_pot_odds_known_hands() {
+        // 河牌AA对KK必胜，跟注50赢下底池200
+        let rsp = pot_odds("AsAh", known("KsKh"), "2c7d9hJdQc");
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.equity, 10000);
+        assert_eq!(rsp.required_equity, 2500);
+        assert_eq!(rsp.call_ev, 150.0);
+        assert_eq!(rsp.bluff_fold_frequency, 3333);
+    }
+
+    #[test]
+    fn test_pot_odds_range() {
+        let rsp = pot_odds("AsAh", range("KK"), "2c7d9hJdQc");
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.equity, 10000);
+        // 翻牌AA对KK大约九成
+        let rsp = pot_odds("AsAh", range("KK,QQ"), "2c7d9h");
+        assert!((8500..9700).contains(&rsp.equity), "{}", rsp.equity);
+        assert!((1..=50000).contains(&rsp.samples), "{}", rsp.samples);
+        let again = pot_odds("AsAh", range("KK,QQ"), "2c7d9h");
+        assert_eq!(rsp.equity, again.equity);
+        assert_eq!(rsp.samples, again.samples);
+        assert!(rsp.call_ev > 0.0);
+    }
+
+    #[test]
+    fn test_pot_odds_ranges_joint() {
+        // 第一个对手AA时第二个对手只能是33，JJ时有5种，互不冲突的6组等概率，
+        // QQ只输给AA，胜率约为5/6，而依次抽取时AA和JJ各占一半
+        let cards = parse_cards("QsQh").unwrap();
+        let req = PotOddsReq {
+            hands: [cards[0], cards[1]],
+            opponents: vec![range("AsAh,JsJh"), range("AsTs,AsTh,AsTc,AsTd,3c3d")],
+            deal_cards: "2c7d9h5s4h".parse().unwrap(),
+            dead_cards: vec![],
+            pot: 100,
+            to_call: 50,
+            seed: Some(11),
+        };
+        let rsp = Evaluator {}.pot_odds(req);
+        assert_eq!(rsp.code, 0);
+        assert!((8150..8500).contains(&rsp.equity), "{}", rsp.equity);
+    }
+
+    #[test]
+    fn test_pot_odds_invalid() {
+        let rsp = pot_odds(
+            "AsAh",
+            Opponent {
+                hands: None,
+                range: None,
+            },
+            "",
+        );
+        assert_eq!(rsp.code, error_model::ERROR_INVALID);
+        let rsp = pot_odds("AsAh", range("AsAh"), "");
+        assert_eq!(rsp.code, error_model::ERROR_INVALID);
+        let rsp = pot_odds("AsAh", known("AsKh"), "");
+        assert_eq!(rsp.code, error_model::ERROR_INVALID);
+
+        // 对手拿走KsKh后只剩4张牌，不够发完公共牌
+        let alive: CardSet = parse_cards("AsAhKsKh2c3c4c5c")
+            .unwrap()
+            .into_iter()
+            .collect();
+        let dead_cards: Vec<Card> = alive.complement().iter().collect();
+        let cards = parse_cards("AsAh").unwrap();
+        let req = PotOddsReq {
+            hands: [cards[0], cards[1]],
+            opponents: vec![range("KK")],
+            deal_cards: "".parse().unwrap(),
+            dead_cards,
+            pot: 100,
+            to_call: 50,
+            seed: Some(7),
+        };
+        assert_eq!(Evaluator {}.pot_odds(req).code, error_model::ERROR_INVALID);
+    }
+}
@@ -0,0 +1,154 @@
+use std::cmp::Reverse;
+
+use crate::models::card::{Board, Card, CardSet, HandCategory, MAX_BOARD};
+use crate::models::error_model;
+use crate::models::model::{
+    BadBeatResult, BadBeatRule, PromotionReq, PromotionRsp, PromotionShare, QualifiedHand,
+    UserCards,
+};
+use crate::services::evaluator::{best_five, Evaluator};
+
+impl Evaluator {
+    pub fn promotion(&self, req: PromotionReq) -> PromotionRsp {
+        let mut rsp = PromotionRsp {
+            code: 0,
+            bad_beat: None,
+            high_hands: vec![],
+            msg: "".to_string(),
+        };
+        if let Err(msg) = promotion_valid(&req) {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = msg;
+            return rsp;
+        }
+        let hands: Vec<QualifiedHand> = req
+            .clients
+            .iter()
+            .map(|x| qualified_hand(x, &req.deal_cards))
+            .collect();
+        if let Some(rule) = &req.rules.high_hand {
+            let min_value = min_hand_value(&rule.min_hand);
+            rsp.high_hands = hands
+                .iter()
+                .filter(|x| meets(x, rule.min_category, min_value, rule.both_hole_cards_play))
+                .cloned()
+                .collect();
+            rsp.high_hands.sort_by_key(|x| Reverse(x.value));
+        }
+        if let Some(rule) = &req.rules.bad_beat {
+            rsp.bad_beat = bad_beat(&hands, &req.table, rule);
+        }
+        return rsp;
+    }
+}
+
+fn promotion_valid(req: &PromotionReq) -> Result<(), String> {
+    if req.clients.is_empty() || req.deal_cards.len() != MAX_BOARD {
+        return Err("req should have clients and a complete board of 5 cards".to_string());
+    }
+    let mut known = req.deal_cards.card_set();
+    for client in &req.clients {
+        for card in client.hands {
+            if !known.insert(card) {
+                return Err(format!("card {} is duplicated", card));
+            }
+        }
+    }
+    let rules = &req.rules;
+    let min_hands = [
+        rules.bad_beat.as_ref().and_then(|x| x.min_hand.as_ref()),
+        rules.high_hand.as_ref().and_then(|x| x.min_hand.as_ref()),
+    ];
+    for min_hand in min_hands.into_iter().flatten() {
+        let set: CardSet = min_hand.iter().copied().collect();
+        if set.len() != min_hand.len() || !(5..=7).contains(&min_hand.len()) {
+            return Err("min_hand should be 5 to 7 distinct cards".to_string());
+        }
+    }
+    if let Some(rule) = &rules.bad_beat {
+        if rule.loser_share + rule.winner_share + rule.table_share > 10000 {
+            return Err("bad beat shares should not be more than 10000".to_string());
+        }
+    }
+    Ok(())
+}
+
+// 优先选用上两张手牌的最大5张牌
+fn qualified_hand(client: &UserCards, board: &Board) -> QualifiedHand {
+    let mut cards = client.hands.to_vec();
+    cards.extend(board.cards());
+    let (value, fives) = best_five(&cards);
+    let both_play = fives
+        .iter()
+        .find(|five| client.hands.iter().all(|x| five.contains(x)));
+    return QualifiedHand {
+        uid: client.uid.clone(),
+        category: HandCategory::of(value),
+        value,
+        best_five: *both_play.unwrap_or(&fives[0]),
+        both_hole_cards_play: both_play.is_some(),
+    };
+}
+
+fn min_hand_value(min_hand: &Option<Vec<Card>>) -> u16 {
+    match min_hand {
+        Some(cards) => best_five(cards).0,
+        None => 0,
+    }
+}
+
+fn meets(hand: &QualifiedHand, min_category: HandCategory, min_value: u16, both: bool) -> bool {
+    return hand.category >= min_category
+        && hand.value >= min_value
+        && (hand.both_hole_cards_play || !both);
+}
+
+// 输家取未赢的玩家中牌力最大的，平分底池时没有输家
+fn bad_beat(
+    hands: &[QualifiedHand],
+    table: &[String],
+    rule: &BadBeatRule,
+) -> Option<BadBeatResult> {
+    let max_value = hands.iter().map(|x| x.value).max()?;
+    let (winners, losers): (Vec<&QualifiedHand>, Vec<&QualifiedHand>) =
+        hands.iter().partition(|x| x.value == max_value);
+    let min_value = min_hand_value(&rule.min_hand);
+    let loser = losers
+        .into_iter()
+        .filter(|x| meets(x, rule.min_category, min_value, rule.both_hole_cards_play))
+        .max_by_key(|x| x.value)?;
+    if rule.winner_hole_cards_play && !winners.iter().all(|x| x.both_hole_cards_play) {
+        return None;
+    }
+    let mut shares = vec![PromotionShare {
+        uid: loser.uid.clone(),
+        role: "loser".to_string(),
+        share: rule.loser_share,
+    }];
+    for winner in &winners {
+        shares.push(PromotionShare {
+            uid: winner.uid.clone(),
+            role: "winner".to_string(),
+            share: rule.winner_share / winners.len() as u64,
+        });
+    }
+    // 桌上份额由其余摊牌玩家和在座玩家平分，除不尽的部分不分配
+    let others: Vec<&String> = hands
+        .iter()
+        .filter(|x| x.uid != loser.uid && x.value != max_value)
+        .map(|x| &x.uid)
+        .chain(table.iter())
+        .collect();
+    for uid in &others {
+        shares.push(PromotionShare {
+            uid: uid.to_string(),
+            role: "table".to_string(),
+            share: rule.table_share / others.len() as u64,
+        });
+    }
+    return Some(BadBeatResult {
+        loser: loser.clone(),
+        winners: winners.into_iter().cloned().collect(),
+        shares,
+    });
+}
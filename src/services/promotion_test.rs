@@ -0,0 +1,96 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::card::{parse_cards, HandCategory};
+    use crate::models::model::{
+        BadBeatRule, HighHandRule, PromotionReq, PromotionRules, UserCards,
+    };
+    use crate::services::evaluator::{best_five, Evaluator};
+
+    fn client(uid: &str, s: &str) -> UserCards {
+        let cards = parse_cards(s).unwrap();
+        UserCards {
+            hands: [cards[0], cards[1]],
+            uid: uid.to_string(),
+        }
+    }
+
+    fn rules() -> PromotionRules {
+        PromotionRules {
+            bad_beat: Some(serde_json::from_str::<BadBeatRule>("{}").unwrap()),
+            high_hand: Some(HighHandRule {
+                min_category: HandCategory::FullHouse,
+                min_hand: None,
+                both_hole_cards_play: true,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_best_five() {
+        // 公共牌四条时踢脚A只有一种组合
+        let (value, fives) = best_five(&parse_cards("As2d8s8h8c8dKs").unwrap());
+        assert_eq!(HandCategory::of(value), HandCategory::FourOfAKind);
+        assert_eq!(fives.len(), 1);
+        assert!(fives[0].contains(&parse_cards("As").unwrap()[0]));
+        // 公共牌同花顺时两张手牌都不用
+        let (_, fives) = best_five(&parse_cards("2d3dAsKsQsJsTs").unwrap());
+        assert_eq!(fives.len(), 1);
+    }
+
+    #[test]
+    fn test_bad_beat() {
+        let req = PromotionReq {
+            clients: vec![
+                client("1", "8c8d"),
+                client("2", "AsTs"),
+                client("3", "KhKd"),
+            ],
+            deal_cards: "8s8hKsQsJs".parse().unwrap(),
+            table: vec!["4".to_string()],
+            rules: rules(),
+        };
+        let rsp = Evaluator {}.promotion(req);
+        assert_eq!(rsp.code, 0);
+        let bad_beat = rsp.bad_beat.unwrap();
+        assert_eq!(bad_beat.loser.uid, "1");
+        assert!(bad_beat.loser.both_hole_cards_play);
+        assert_eq!(bad_beat.winners[0].category, HandCategory::StraightFlush);
+        let shares: Vec<(&str, &str, u64)> = bad_beat
+            .shares
+            .iter()
+            .map(|x| (x.uid.as_str(), x.role.as_str(), x.share))
+            .collect();
+        assert_eq!(
+            shares,
+            vec![
+                ("1", "loser", 5000),
+                ("2", "winner", 2500),
+                ("3", "table", 1250),
+                ("4", "table", 1250)
+            ]
+        );
+        let high_hands: Vec<&str> = rsp.high_hands.iter().map(|x| x.uid.as_str()).collect();
+        assert_eq!(high_hands, vec!["2", "1", "3"]);
+    }
+
+    #[test]
+    fn test_bad_beat_one_hole_card() {
+        let new_req = |both_hole_cards_play: bool| {
+            let mut rules = rules();
+            rules.bad_beat.as_mut().unwrap().both_hole_cards_play = both_hole_cards_play;
+            PromotionReq {
+                clients: vec![client("1", "8d2c"), client("2", "9sTs")],
+                deal_cards: "8h8c8sQsJs".parse().unwrap(),
+                table: vec![],
+                rules,
+            }
+        };
+        let evaluator = Evaluator {};
+        let rsp = evaluator.promotion(new_req(true));
+        assert!(rsp.bad_beat.is_none());
+        assert_eq!(rsp.high_hands.len(), 1);
+        assert_eq!(rsp.high_hands[0].uid, "2");
+        let rsp = evaluator.promotion(new_req(false));
+        assert!(!rsp.bad_beat.unwrap().loser.both_hole_cards_play);
+    }
+}
@@ -0,0 +1,342 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::thread;
+
+use holdem_hand_evaluator::Hand;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::models::card::{Card, CardSet, Rank, MAX_BOARD};
+use crate::models::error_model;
+use crate::models::model::{PushFoldAction, PushFoldChart, PushFoldReq, PushFoldRsp};
+use crate::models::range::HandClass;
+use crate::services::evaluator::{sample_board, Evaluator};
+use crate::utils::log::{log_error_display, log_info_display};
+use crate::utils::store::{data_dir, read_json, write_json};
+
+// 支持的人数，多人时只考虑第一个跟注的人，之后的人都弃牌
+pub const MAX_PUSH_FOLD_PLAYERS: usize = 6;
+
+// 有效筹码和前注的范围，单位为大盲
+const MAX_STACK: f64 = 50.0;
+const MAX_ANTE: f64 = 1.0;
+
+// 起手牌类别两两之间胜率的采样次数，结果缓存在磁盘上
+const EQUITY_SAMPLES: u64 = 2000;
+const EQUITY_SEED: u64 = 169;
+
+// 虚拟博弈的迭代次数
+const ITERATIONS: usize = 300;
+
+// 缓存格式或求解方法变化时加一，与采样和迭代参数一起写进缓存文件名，旧的缓存不再使用
+const CACHE_VERSION: u32 = 1;
+
+// 从前往后的位置名，取最后n个
+const POSITIONS: [&str; MAX_PUSH_FOLD_PLAYERS] = ["UTG", "HJ", "CO", "BTN", "SB", "BB"];
+
+const CLASSES: usize = 169;
+
+/// 169种起手牌类别两两之间的胜率，下标与HandClass::all()一致
+#[derive(Deserialize, Serialize)]
+pub struct PreflopEquity {
+    equity: Vec<u16>, // equity[a * 169 + b]为a对b的胜率，平局算一半，10000为分母
+    weight: Vec<u16>, // 两个类别之间不冲突的组合对数
+}
+
+impl PreflopEquity {
+    // 每对类别随机抽取不冲突的组合和公共牌，a对b与b对a互补
+    pub fn compute(samples: u64, seed: u64) -> PreflopEquity {
+        let classes = HandClass::all();
+        let combos: Vec<Vec<CardSet>> = classes
+            .iter()
+            .map(|x| {
+                x.combos()
+                    .iter()
+                    .map(|c| c.iter().copied().collect())
+                    .collect()
+            })
+            .collect();
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut deck: Vec<Card> = Card::all().collect();
+        let mut equity = vec![5000u16; CLASSES * CLASSES];
+        let mut weight = vec![0u16; CLASSES * CLASSES];
+        for a in 0..CLASSES {
+            for b in a..CLASSES {
+                let pairs: Vec<(CardSet, CardSet)> = combos[a]
+                    .iter()
+                    .flat_map(|x| combos[b].iter().map(move |y| (*x, *y)))
+                    .filter(|(x, y)| x.mask() & y.mask() == 0)
+                    .collect();
+                weight[a * CLASSES + b] = pairs.len() as u16;
+                weight[b * CLASSES + a] = pairs.len() as u16;
+                if a == b || pairs.is_empty() {
+                    continue;
+                }
+                let mut score = 0u64;
+                for _ in 0..samples {
+                    let (x, y) = pairs[rng.gen_range(0..pairs.len())];
+                    let used = x.union(y);
+                    // 从整副牌抽公共牌，与手牌冲突时重抽，避免每次重建牌堆
+                    let board = loop {
+                        let board = sample_board(&mut rng, Hand::new(), &mut deck, MAX_BOARD);
+                        if deck[..MAX_BOARD].iter().all(|c| !used.contains(*c)) {
+                            break board;
+                        }
+                    };
+                    let ours = (x.hand() + board).evaluate();
+                    let theirs = (y.hand() + board).evaluate();
+                    score += match ours.cmp(&theirs) {
+                        Ordering::Greater => 2,
+                        Ordering::Equal => 1,
+                        Ordering::Less => 0,
+                    };
+                }
+                let rate = (score * 5000 / samples) as u16;
+                equity[a * CLASSES + b] = rate;
+                equity[b * CLASSES + a] = 10000 - rate;
+            }
+        }
+        PreflopEquity { equity, weight }
+    }
+
+    fn equity(&self, a: usize, b: usize) -> f64 {
+        self.equity[a * CLASSES + b] as f64 / 10000.0
+    }
+
+    fn weight(&self, a: usize, b: usize) -> f64 {
+        self.weight[a * CLASSES + b] as f64
+    }
+
+    // 类别h对按频率加权的范围的胜率，以及对手在范围内的概率
+    fn vs_range(&self, h: usize, range: &[f64]) -> (f64, f64) {
+        let (mut total, mut inside, mut score) = (0.0, 0.0, 0.0);
+        for (c, freq) in range.iter().enumerate() {
+            let w = self.weight(h, c);
+            total += w;
+            inside += w * freq;
+            score += w * freq * self.equity(h, c);
+        }
+        if inside == 0.0 {
+            return (0.0, 0.0);
+        }
+        (score / inside, inside / total)
+    }
+}
+
+impl Evaluator {
+    pub fn push_fold(&self, req: PushFoldReq) -> PushFoldRsp {
+        let mut rsp = PushFoldRsp {
+            code: 0,
+            charts: vec![],
+            cached: false,
+            msg: "".to_string(),
+        };
+        if let Err(msg) = push_fold_valid(&req) {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = msg;
+            return rsp;
+        }
+        let path = data_dir("cache").join(format!(
+            "pushfold_v{}_{}_{}_{}_{}_{}.json",
+            CACHE_VERSION,
+            ITERATIONS,
+            EQUITY_SAMPLES,
+            req.players,
+            (req.stack * 100.0).round() as u64,
+            (req.ante * 100.0).round() as u64
+        ));
+        if let Some(charts) = read_json::<Vec<PushFoldChart>>(&path) {
+            rsp.charts = charts;
+            rsp.cached = true;
+            return rsp;
+        }
+        rsp.charts = solve_push_fold(preflop_equity(), &req);
+        write_cache(&path, &rsp.charts);
+        return rsp;
+    }
+}
+
+pub fn push_fold_valid(req: &PushFoldReq) -> Result<(), String> {
+    if !(2..=MAX_PUSH_FOLD_PLAYERS).contains(&req.players) {
+        return Err(format!(
+            "players should be between 2 and {}",
+            MAX_PUSH_FOLD_PLAYERS
+        ));
+    }
+    if !(0.0..=MAX_ANTE).contains(&req.ante) || !(req.ante + 1.0..=MAX_STACK).contains(&req.stack) {
+        return Err(format!(
+            "ante should be between 0 and {}, stack should be more than ante and blind and at most {}",
+            MAX_ANTE, MAX_STACK
+        ));
+    }
+    Ok(())
+}
+
+// 写缓存失败只记日志，不影响本次结果
+fn write_cache<T: Serialize>(path: &Path, value: &T) {
+    if let Err(err) = write_json(path, value) {
+        log_error_display("write push fold cache", &err);
+    }
+}
+
+// 服务启动时在后台加载或计算起手牌胜率，避免第一个请求超时
+pub fn warm_up() {
+    thread::spawn(|| {
+        preflop_equity();
+    });
+}
+
+// 进程内只加载或计算一次
+fn preflop_equity() -> &'static PreflopEquity {
+    static EQUITY: OnceLock<PreflopEquity> = OnceLock::new();
+    EQUITY.get_or_init(|| {
+        let path = data_dir("cache").join(format!(
+            "preflop_equity_v{}_{}_{}.json",
+            CACHE_VERSION, EQUITY_SAMPLES, EQUITY_SEED
+        ));
+        if let Some(equity) = read_json::<PreflopEquity>(&path) {
+            return equity;
+        }
+        log_info_display("compute preflop equity samples", &EQUITY_SAMPLES);
+        let equity = PreflopEquity::compute(EQUITY_SAMPLES, EQUITY_SEED);
+        write_cache(&path, &equity);
+        equity
+    })
+}
+
+fn blind(position: usize, players: usize) -> f64 {
+    if position == players - 1 {
+        1.0
+    } else if position == players - 2 {
+        0.5
+    } else {
+        0.0
+    }
+}
+
+// 前面的人都弃牌时各位置全下或弃牌，面对全下时后面的位置依次跟注或弃牌，用虚拟博弈求近似均衡
+pub fn solve_push_fold(equity: &PreflopEquity, req: &PushFoldReq) -> Vec<PushFoldChart> {
+    let n = req.players;
+    let (stack, ante) = (req.stack, req.ante);
+    let dead = n as f64 * ante + 1.5;
+    let fold_stack = |k: usize| stack - ante - blind(k, n);
+    // i全下j跟注时的底池，其他人只有盲注和前注
+    let pot = |i: usize, j: usize| 2.0 * stack + dead - 2.0 * ante - blind(i, n) - blind(j, n);
+    let mut push = vec![vec![1.0; CLASSES]; n - 1];
+    let mut call: HashMap<(usize, usize), Vec<f64>> = HashMap::new();
+    for i in 0..n - 1 {
+        for j in i + 1..n {
+            call.insert((i, j), vec![0.0; CLASSES]);
+        }
+    }
+    for t in 1..=ITERATIONS {
+        // 第一次迭代直接取最优反应，替换初始猜测
+        let step = 1.0 / t as f64;
+        let mut call_best = HashMap::new();
+        for (&(i, j), _) in call.iter() {
+            let best: Vec<f64> = (0..CLASSES)
+                .map(|h| {
+                    let (eq, inside) = equity.vs_range(h, &push[i]);
+                    if inside > 0.0 && eq * pot(i, j) > fold_stack(j) {
+                        1.0
+                    } else {
+                        0.0
+                    }
+                })
+                .collect();
+            call_best.insert((i, j), best);
+        }
+        let mut push_best = vec![];
+        for i in 0..n - 1 {
+            let best: Vec<f64> = (0..CLASSES)
+                .map(|h| {
+                    let mut all_fold = 1.0;
+                    let mut ev = 0.0;
+                    for j in i + 1..n {
+                        let (eq, called) = equity.vs_range(h, &call[&(i, j)]);
+                        ev += all_fold * called * eq * pot(i, j);
+                        all_fold *= 1.0 - called;
+                    }
+                    ev += all_fold * (fold_stack(i) + dead);
+                    if ev > fold_stack(i) {
+                        1.0
+                    } else {
+                        0.0
+                    }
+                })
+                .collect();
+            push_best.push(best);
+        }
+        for (key, best) in call_best {
+            let avg = call.get_mut(&key).unwrap();
+            avg.iter_mut()
+                .zip(best)
+                .for_each(|(x, y)| *x += (y - *x) * step);
+        }
+        for (avg, best) in push.iter_mut().zip(push_best) {
+            avg.iter_mut()
+                .zip(best)
+                .for_each(|(x, y)| *x += (y - *x) * step);
+        }
+    }
+    let position = |k: usize| POSITIONS[MAX_PUSH_FOLD_PLAYERS - n + k].to_string();
+    let mut charts = vec![];
+    for (i, freq) in push.iter().enumerate() {
+        charts.push(chart(position(i), PushFoldAction::Push, None, freq));
+        for j in i + 1..n {
+            charts.push(chart(
+                position(j),
+                PushFoldAction::Call,
+                Some(position(i)),
+                &call[&(i, j)],
+            ));
+        }
+    }
+    charts
+}
+
+fn chart(
+    position: String,
+    action: PushFoldAction,
+    vs: Option<String>,
+    freq: &[f64],
+) -> PushFoldChart {
+    let classes = HandClass::all();
+    let to_rate = |x: f64| (x * 10000.0).round() as u64;
+    let index: HashMap<HandClass, usize> =
+        classes.iter().enumerate().map(|(i, x)| (*x, i)).collect();
+    // 行列从A到2，行小于列为同花
+    let rank = |i: usize| Rank::ALL[Rank::ALL.len() - 1 - i];
+    let grid = (0..13)
+        .map(|row| {
+            (0..13)
+                .map(|col| {
+                    let class = HandClass::new(rank(row), rank(col), row < col);
+                    to_rate(freq[index[&class]])
+                })
+                .collect()
+        })
+        .collect();
+    let combos: f64 = classes
+        .iter()
+        .zip(freq)
+        .map(|(x, f)| x.combos().len() as f64 * f)
+        .sum();
+    PushFoldChart {
+        position,
+        action,
+        vs,
+        percent: to_rate(combos / 1326.0),
+        range: classes
+            .iter()
+            .zip(freq)
+            .filter(|(_, f)| **f >= 0.5)
+            .map(|(x, _)| x.to_string())
+            .collect::<Vec<String>>()
+            .join(","),
+        grid,
+    }
+}
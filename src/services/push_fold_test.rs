@@ -0,0 +1,80 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use crate::models::model::{PushFoldAction, PushFoldChart, PushFoldReq};
+    use crate::services::push_fold::{push_fold_valid, solve_push_fold, PreflopEquity};
+
+    fn req(players: usize, stack: f64, ante: f64) -> PushFoldReq {
+        PushFoldReq {
+            players,
+            stack,
+            ante,
+        }
+    }
+
+    // 测试只用少量采样，只检查明显的手牌，各测试共用
+    fn equity() -> &'static PreflopEquity {
+        static EQUITY: OnceLock<PreflopEquity> = OnceLock::new();
+        EQUITY.get_or_init(|| PreflopEquity::compute(20, 1))
+    }
+
+    // grid的行列从A到2
+    fn cell(chart: &PushFoldChart, row: usize, col: usize) -> u64 {
+        chart.grid[row][col]
+    }
+
+    #[test]
+    fn test_push_fold_heads_up() {
+        let charts = solve_push_fold(equity(), &req(2, 10.0, 0.0));
+        assert_eq!(charts.len(), 2);
+        let (push, call) = (&charts[0], &charts[1]);
+        assert_eq!(
+            (push.position.as_str(), push.action),
+            ("SB", PushFoldAction::Push)
+        );
+        assert_eq!(call.action, PushFoldAction::Call);
+        assert_eq!(call.vs.as_deref(), Some("SB"));
+        assert_eq!(push.grid.len(), 13);
+        // AA全下和跟注，32o跟注时弃牌
+        assert_eq!(cell(push, 0, 0), 10000);
+        assert_eq!(cell(call, 0, 0), 10000);
+        assert_eq!(cell(call, 12, 11), 0);
+        assert!(push.range.starts_with("AA,AKs,AKo"));
+        assert!(push.percent > call.percent);
+
+        // 筹码很少时几乎全部全下
+        let charts = solve_push_fold(equity(), &req(2, 1.5, 0.0));
+        assert!(charts[0].percent > 9000, "{}", charts[0].percent);
+    }
+
+    #[test]
+    fn test_push_fold_multiway() {
+        let charts = solve_push_fold(equity(), &req(3, 10.0, 0.1));
+        let names: Vec<(String, Option<String>)> = charts
+            .iter()
+            .map(|x| (x.position.clone(), x.vs.clone()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("BTN".to_string(), None),
+                ("SB".to_string(), Some("BTN".to_string())),
+                ("BB".to_string(), Some("BTN".to_string())),
+                ("SB".to_string(), None),
+                ("BB".to_string(), Some("SB".to_string())),
+            ]
+        );
+        // 后面还有两个人时全下范围更紧
+        assert!(charts[0].percent < charts[3].percent);
+    }
+
+    #[test]
+    fn test_push_fold_valid() {
+        assert!(push_fold_valid(&req(2, 10.0, 0.0)).is_ok());
+        assert!(push_fold_valid(&req(1, 10.0, 0.0)).is_err());
+        assert!(push_fold_valid(&req(7, 10.0, 0.0)).is_err());
+        assert!(push_fold_valid(&req(2, 0.5, 0.0)).is_err());
+        assert!(push_fold_valid(&req(2, 100.0, 0.0)).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use crate::models::card::{Board, Card, CardSet, HandCategory, MAX_BOARD};
+use crate::models::error_model;
+use crate::models::model::{RabbitHuntReq, RabbitHuntRsp, ShowdownHand, ShuffleSeeds};
+use crate::services::evaluator::{calculate_rating_valid, showdown_winners, Evaluator};
+use crate::services::shuffle::{deal, players_valid, shuffle_deck, MAX_PLAYERS};
+
+impl Evaluator {
+    pub fn rabbit_hunt(&self, req: RabbitHuntReq) -> RabbitHuntRsp {
+        let mut rsp = RabbitHuntRsp {
+            code: 0,
+            rabbit_cards: vec![],
+            deal_cards: Board::default(),
+            clients: vec![],
+            winners: vec![],
+            msg: "".to_string(),
+        };
+        let rating_req = req.to_rating_req();
+        let (valid, user_cards) = calculate_rating_valid(&rating_req);
+        if !valid || req.deal_cards.len() == MAX_BOARD {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = "req has duplicates or client.len is lt 2 or board is complete".to_string();
+            return rsp;
+        }
+        let (_, alive_cards) =
+            self.get_board_and_alive_cards(&req.deal_cards, &req.dead_cards, &req.clients);
+        let remain_card = MAX_BOARD - req.deal_cards.len();
+        let rabbit_cards = match (&req.shuffle, req.deck.is_empty()) {
+            (Some(seeds), true) => rabbit_cards_from_seeds(&req, seeds),
+            (None, false) => rabbit_cards_from_deck(&req, &alive_cards, remain_card),
+            _ => Err("exactly one of deck and shuffle should be given".to_string()),
+        };
+        match rabbit_cards {
+            Ok(cards) => rsp.rabbit_cards = cards,
+            Err(msg) => {
+                rsp.code = error_model::ERROR_INVALID;
+                rsp.msg = msg;
+                return rsp;
+            }
+        }
+        let mut cards = req.deal_cards.cards().to_vec();
+        cards.extend(&rsp.rabbit_cards);
+        rsp.deal_cards = Board::new(cards).unwrap();
+        let new_board = rsp.deal_cards.hand();
+        for user_card in &user_cards {
+            let value = (user_card.hands + new_board).evaluate();
+            rsp.clients.push(ShowdownHand {
+                uid: user_card.uid.clone(),
+                category: HandCategory::of(value),
+                value,
+            });
+        }
+        rsp.winners = showdown_winners(&user_cards, new_board)
+            .into_iter()
+            .map(|i| user_cards[i].uid.clone())
+            .collect();
+        return rsp;
+    }
+}
+
+// 按给定的牌堆顺序补发公共牌，烧牌时翻牌前烧一张，转牌和河牌前各烧一张
+fn rabbit_cards_from_deck(
+    req: &RabbitHuntReq,
+    alive_cards: &[Card],
+    remain_card: usize,
+) -> Result<Vec<Card>, String> {
+    let alive: CardSet = alive_cards.iter().copied().collect();
+    let deck: CardSet = req.deck.iter().copied().collect();
+    if deck.len() != req.deck.len() || !req.deck.iter().all(|x| alive.contains(*x)) {
+        return Err("deck has duplicates or cards already dealt".to_string());
+    }
+    let mut deck = req.deck.iter();
+    let mut cards = vec![];
+    let mut deal_len = req.deal_cards.len();
+    while cards.len() < remain_card {
+        // 每街第一张牌前烧牌，翻牌一次发3张
+        let street_len = if deal_len == 0 { 3 } else { 1 };
+        if req.burn {
+            deck.next().ok_or("deck has not enough cards")?;
+        }
+        for _ in 0..street_len {
+            cards.push(*deck.next().ok_or("deck has not enough cards")?);
+        }
+        deal_len += street_len;
+    }
+    return Ok(cards);
+}
+
+// 由种子还原这手牌实际的洗牌结果，已发的公共牌和玩家手牌要与之一致，剩余的公共牌即为会发的牌
+fn rabbit_cards_from_seeds(req: &RabbitHuntReq, seeds: &ShuffleSeeds) -> Result<Vec<Card>, String> {
+    if !players_valid(seeds.players) {
+        return Err(format!("players should be between 2 and {}", MAX_PLAYERS));
+    }
+    let deck = shuffle_deck(&seeds.server_seed, &seeds.client_seed, seeds.nonce);
+    let (hands, board) = deal(&deck, seeds.players);
+    let dealt = board.prefix(req.deal_cards.len());
+    if dealt.card_set() != req.deal_cards.card_set() {
+        return Err(format!("deal cards should be {}", dealt));
+    }
+    let seated: CardSet = hands.iter().flatten().copied().collect();
+    if req
+        .clients
+        .iter()
+        .flat_map(|x| x.hands)
+        .any(|x| !seated.contains(x))
+    {
+        return Err("client hands are not dealt by the shuffle seeds".to_string());
+    }
+    let rabbit_cards = board.cards()[req.deal_cards.len()..].to_vec();
+    if rabbit_cards.iter().any(|x| req.dead_cards.contains(x)) {
+        return Err("dead cards conflict with the remaining board".to_string());
+    }
+    return Ok(rabbit_cards);
+}
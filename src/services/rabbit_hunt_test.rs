@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::card::{parse_cards, Board, HandCategory};
+    use crate::models::error_model;
+    use crate::models::model::{RabbitHuntReq, ShuffleSeeds, UserCards};
+    use crate::services::evaluator::Evaluator;
+    use crate::services::shuffle::{deal, shuffle_deck};
+
+    fn new_req(deck: &str, burn: bool) -> RabbitHuntReq {
+        let client = |uid: &str, s: &str| {
+            let cards = parse_cards(s).unwrap();
+            UserCards {
+                hands: [cards[0], cards[1]],
+                uid: uid.to_string(),
+            }
+        };
+        RabbitHuntReq {
+            clients: vec![client("1", "AsAd"), client("2", "KsKd")],
+            deal_cards: "Ah7c2d".parse().unwrap(),
+            dead_cards: vec![],
+            deck: parse_cards(deck).unwrap(),
+            burn,
+            shuffle: None,
+        }
+    }
+
+    #[test]
+    fn test_rabbit_hunt_deck() {
+        let evaluator = Evaluator {};
+        let rsp = evaluator.rabbit_hunt(new_req("KhKc", false));
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.deal_cards.to_string(), "Ah7c2dKhKc");
+        assert_eq!(rsp.winners, vec!["2"]);
+        assert_eq!(rsp.clients[1].category, HandCategory::FourOfAKind);
+
+        // 烧牌后发3s和4s，AA的三条赢
+        let rsp = evaluator.rabbit_hunt(new_req("Kh3sKc4s", true));
+        assert_eq!(rsp.rabbit_cards, parse_cards("3s4s").unwrap());
+        assert_eq!(rsp.winners, vec!["1"]);
+
+        assert_ne!(evaluator.rabbit_hunt(new_req("Kh", false)).code, 0);
+        assert_ne!(evaluator.rabbit_hunt(new_req("KhAd", false)).code, 0);
+    }
+
+    fn seeds() -> ShuffleSeeds {
+        ShuffleSeeds {
+            server_seed: "server".to_string(),
+            client_seed: "client".to_string(),
+            nonce: 3,
+            players: 4,
+        }
+    }
+
+    #[test]
+    fn test_rabbit_hunt_seed() {
+        // 由种子还原实际的发牌，翻牌后补发的就是这手牌的转牌和河牌
+        let evaluator = Evaluator {};
+        let deck = shuffle_deck("server", "client", 3);
+        let (hands, board) = deal(&deck, 4);
+        let mut req = new_req("", false);
+        req.clients[0].hands = hands[0];
+        req.clients[1].hands = hands[2];
+        req.deal_cards = board.prefix(3);
+        req.shuffle = Some(seeds());
+        let rsp = evaluator.rabbit_hunt(req);
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.rabbit_cards, board.cards()[3..].to_vec());
+        assert_eq!(rsp.deal_cards, board);
+
+        // 已发的公共牌或手牌与种子不一致
+        let mut req = new_req("", false);
+        req.clients[0].hands = hands[0];
+        req.clients[1].hands = hands[2];
+        req.deal_cards = Board::new(board.cards()[1..4].to_vec()).unwrap();
+        req.shuffle = Some(seeds());
+        assert_eq!(evaluator.rabbit_hunt(req).code, error_model::ERROR_INVALID);
+        let mut req = new_req("", false);
+        req.deal_cards = board.prefix(3);
+        req.shuffle = Some(seeds());
+        assert_eq!(evaluator.rabbit_hunt(req).code, error_model::ERROR_INVALID);
+
+        // deck和shuffle必须且只能给一个
+        assert_eq!(
+            evaluator.rabbit_hunt(new_req("", false)).code,
+            error_model::ERROR_INVALID
+        );
+        let mut req = new_req("KhKc", false);
+        req.shuffle = Some(seeds());
+        assert_eq!(evaluator.rabbit_hunt(req).code, error_model::ERROR_INVALID);
+    }
+}
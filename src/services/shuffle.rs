@@ -0,0 +1,395 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use rand::{thread_rng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::models::card::{Board, Card, DECK_SIZE, MAX_BOARD};
+use crate::models::error_model;
+use crate::models::model::{
+    ShuffleBoardReq, ShuffleBoardRsp, ShuffleCommitRsp, ShuffleDealReq, ShuffleDealRsp,
+    ShuffleRevealReq, ShuffleRevealRsp, Street, VerifyShuffleReq, VerifyShuffleRsp,
+};
+use crate::utils::log::log_error_display;
+use crate::utils::store::{data_dir, read_json, write_json};
+
+// 发牌时每人两张，公共牌前烧3张
+pub const MAX_PLAYERS: usize = (DECK_SIZE - MAX_BOARD - 3) / 2;
+
+// 公开后保留一段时间，重复公开返回相同的结果
+pub(crate) const REVEALED_TTL: Duration = Duration::from_secs(24 * 3600);
+// 没有公开的手牌(如游戏服务异常退出)最后一次写入后保留的时间
+pub(crate) const UNREVEALED_TTL: Duration = Duration::from_secs(7 * 24 * 3600);
+// 清理过期文件的间隔
+const SWEEP_INTERVAL: Duration = Duration::from_secs(3600);
+
+// 每手牌的种子存为一个文件，过期前重启或多个实例共享目录时都能公开
+#[derive(Deserialize, Serialize)]
+struct HandSeed {
+    server_seed: String,
+    token_hash: String, // sha256(token)，只有创建这手牌的游戏服务能发牌和公开种子
+    client_seed: Option<(String, u64)>,
+    players: usize,
+    revealed: bool,
+}
+
+// 同一进程内同一手牌串行读写，不同手牌互不阻塞；多个实例同时给同一手牌发牌时以后写入的为准
+static HAND_LOCKS: Mutex<BTreeMap<PathBuf, Weak<Mutex<()>>>> = Mutex::new(BTreeMap::new());
+
+fn hand_lock(path: &Path) -> Arc<Mutex<()>> {
+    let mut locks = HAND_LOCKS.lock().unwrap();
+    // 没有人持有的锁随时清掉，只保留正在处理的手牌
+    locks.retain(|_, x| x.strong_count() > 0);
+    if let Some(lock) = locks.get(path).and_then(Weak::upgrade) {
+        return lock;
+    }
+    let lock = Arc::new(Mutex::new(()));
+    locks.insert(path.to_path_buf(), Arc::downgrade(&lock));
+    return lock;
+}
+
+fn hand_path(hand_id: &str) -> Result<PathBuf, String> {
+    // hand_id必须是uuid，避免拼出其他路径
+    let hand_id = Uuid::parse_str(hand_id).map_err(|_| "hand_id is invalid".to_string())?;
+    Ok(data_dir("shuffle").join(format!("{}.json", hand_id)))
+}
+
+fn random_hex() -> String {
+    let mut bytes = [0u8; 32];
+    thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+// 读取这手牌并校验token，调用方需持有这手牌的锁
+fn load_hand(path: &Path, token: &str) -> Result<HandSeed, String> {
+    let Some(hand) = read_json::<HandSeed>(path) else {
+        return Err("hand_id is not committed".to_string());
+    };
+    if sha256_hex(token.as_bytes()) != hand.token_hash {
+        return Err("token does not match".to_string());
+    }
+    Ok(hand)
+}
+
+fn save_hand(path: &Path, hand: &HandSeed) -> Result<(), String> {
+    write_json(path, hand).map_err(|err| {
+        log_error_display("save shuffle seed", &err);
+        "failed to save seed".to_string()
+    })
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    return Sha256::digest(data)
+        .iter()
+        .map(|x| format!("{:02x}", x))
+        .collect();
+}
+
+// 随机数流: 第k块为sha256("server_seed:client_seed:nonce:k")，每4字节按大端序取一个u32
+pub struct SeedStream {
+    key: String,
+    counter: u64,
+    block: [u8; 32],
+    offset: usize,
+}
+
+impl SeedStream {
+    pub fn new(server_seed: &str, client_seed: &str, nonce: u64) -> SeedStream {
+        return SeedStream {
+            key: format!("{}:{}:{}", server_seed, client_seed, nonce),
+            counter: 0,
+            block: [0; 32],
+            offset: 32,
+        };
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        if self.offset == self.block.len() {
+            let data = format!("{}:{}", self.key, self.counter);
+            self.block = Sha256::digest(data.as_bytes()).into();
+            self.counter += 1;
+            self.offset = 0;
+        }
+        let bytes = &self.block[self.offset..self.offset + 4];
+        self.offset += 4;
+        return u32::from_be_bytes(bytes.try_into().unwrap());
+    }
+
+    // [0, n)的均匀随机数，超出2^32中n的整数倍部分的值丢弃重取，避免取模偏差
+    pub fn below(&mut self, n: u32) -> u32 {
+        let limit = (1u64 << 32) - (1u64 << 32) % n as u64;
+        loop {
+            let x = self.next_u32();
+            if (x as u64) < limit {
+                return x % n;
+            }
+        }
+    }
+}
+
+// 从按id排列的整副牌(2s,2h,2c,2d,3s...)开始做Fisher–Yates洗牌
+pub fn shuffle_deck(server_seed: &str, client_seed: &str, nonce: u64) -> Vec<Card> {
+    let mut stream = SeedStream::new(server_seed, client_seed, nonce);
+    let mut deck: Vec<Card> = Card::all().collect();
+    for i in (1..deck.len()).rev() {
+        let j = stream.below(i as u32 + 1) as usize;
+        deck.swap(i, j);
+    }
+    return deck;
+}
+
+// 按座位轮流发两圈手牌，然后烧一张发翻牌，烧一张发转牌，烧一张发河牌
+pub fn deal(deck: &[Card], players: usize) -> (Vec<[Card; 2]>, Board) {
+    let hands = (0..players)
+        .map(|seat| [deck[seat], deck[seat + players]])
+        .collect();
+    let pos = 2 * players;
+    let board = [pos + 1, pos + 2, pos + 3, pos + 5, pos + 7]
+        .iter()
+        .map(|&i| deck[i])
+        .collect();
+    return (hands, Board::new(board).unwrap());
+}
+
+pub(crate) fn players_valid(players: usize) -> bool {
+    return (2..=MAX_PLAYERS).contains(&players);
+}
+
+// 生成服务端种子，只返回其承诺和发牌、公开种子用的token
+pub fn shuffle_commit() -> ShuffleCommitRsp {
+    let mut rsp = ShuffleCommitRsp {
+        code: 0,
+        hand_id: Uuid::new_v4().to_string(),
+        commitment: "".to_string(),
+        token: random_hex(),
+        msg: "".to_string(),
+    };
+    let hand = HandSeed {
+        server_seed: random_hex(),
+        token_hash: sha256_hex(rsp.token.as_bytes()),
+        client_seed: None,
+        players: 0,
+        revealed: false,
+    };
+    if let Err(msg) = save_hand(&hand_path(&rsp.hand_id).unwrap(), &hand) {
+        rsp.code = error_model::ERROR_INVALID;
+        rsp.msg = msg;
+        rsp.hand_id.clear();
+        rsp.token.clear();
+        return rsp;
+    }
+    rsp.commitment = sha256_hex(hand.server_seed.as_bytes());
+    return rsp;
+}
+
+// 第一次发牌时确定玩家种子和人数，之后只能用相同的种子和人数发其他座位
+fn dealt_deck(
+    hand_id: &str,
+    token: &str,
+    client_seed: Option<(String, u64, usize)>,
+) -> Result<(Vec<Card>, usize), String> {
+    let path = hand_path(hand_id)?;
+    let lock = hand_lock(&path);
+    let _guard = lock.lock().unwrap();
+    let mut hand = load_hand(&path, token)?;
+    if hand.revealed {
+        return Err("hand is already revealed".to_string());
+    }
+    match (client_seed, &hand.client_seed) {
+        (Some((seed, nonce, players)), None) => {
+            hand.client_seed = Some((seed, nonce));
+            hand.players = players;
+            save_hand(&path, &hand)?;
+        }
+        (Some((seed, nonce, players)), Some(dealt)) => {
+            if dealt.0 != seed || dealt.1 != nonce || hand.players != players {
+                return Err("hand is already dealt with another client seed".to_string());
+            }
+        }
+        (None, None) => return Err("hand is not dealt yet".to_string()),
+        (None, Some(_)) => {}
+    }
+    let (seed, nonce) = hand.client_seed.as_ref().unwrap();
+    Ok((shuffle_deck(&hand.server_seed, seed, *nonce), hand.players))
+}
+
+// 用玩家种子洗牌，只返回请求的座位的手牌，整副牌留在服务端
+pub fn shuffle_deal(req: ShuffleDealReq) -> ShuffleDealRsp {
+    let mut rsp = ShuffleDealRsp {
+        code: 0,
+        seat: req.seat,
+        hands: None,
+        msg: "".to_string(),
+    };
+    if !players_valid(req.players) || !(1..=req.players).contains(&req.seat) {
+        rsp.code = error_model::ERROR_INVALID;
+        rsp.msg = format!(
+            "players should be between 2 and {}, and seat between 1 and players",
+            MAX_PLAYERS
+        );
+        return rsp;
+    }
+    let client_seed = Some((req.client_seed, req.nonce, req.players));
+    match dealt_deck(&req.hand_id, &req.token, client_seed) {
+        Ok((deck, players)) => {
+            let (hands, _) = deal(&deck, players);
+            rsp.hands = Some(hands[req.seat - 1]);
+        }
+        Err(msg) => {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = msg;
+        }
+    }
+    return rsp;
+}
+
+// 发到某条街为止的公共牌，之后的牌不返回
+pub fn shuffle_board(req: ShuffleBoardReq) -> ShuffleBoardRsp {
+    let mut rsp = ShuffleBoardRsp {
+        code: 0,
+        board: Board::default(),
+        msg: "".to_string(),
+    };
+    match dealt_deck(&req.hand_id, &req.token, None) {
+        Ok((deck, players)) => {
+            let (_, board) = deal(&deck, players);
+            let len = match req.street {
+                Street::Preflop => 0,
+                Street::Flop => 3,
+                Street::Turn => 4,
+                Street::River => 5,
+            };
+            rsp.board = board.prefix(len);
+        }
+        Err(msg) => {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = msg;
+        }
+    }
+    return rsp;
+}
+
+// 牌局结束后由游戏服务公开种子，之后这手牌不能再发，重复公开返回相同的结果
+pub fn shuffle_reveal(req: ShuffleRevealReq) -> ShuffleRevealRsp {
+    let mut rsp = ShuffleRevealRsp {
+        code: 0,
+        server_seed: "".to_string(),
+        commitment: "".to_string(),
+        client_seed: "".to_string(),
+        nonce: 0,
+        msg: "".to_string(),
+    };
+    let loaded = hand_path(&req.hand_id).and_then(|path| {
+        let lock = hand_lock(&path);
+        let _guard = lock.lock().unwrap();
+        let mut hand = load_hand(&path, &req.token)?;
+        if !hand.revealed {
+            hand.revealed = true;
+            save_hand(&path, &hand)?;
+        }
+        Ok(hand)
+    });
+    let hand = match loaded {
+        Ok(hand) => hand,
+        Err(msg) => {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = msg;
+            return rsp;
+        }
+    };
+    rsp.commitment = sha256_hex(hand.server_seed.as_bytes());
+    rsp.server_seed = hand.server_seed;
+    if let Some((client_seed, nonce)) = hand.client_seed {
+        rsp.client_seed = client_seed;
+        rsp.nonce = nonce;
+    }
+    return rsp;
+}
+
+// 按最后一次写入的时间删除过期的手牌，公开后写入的就是公开时间
+pub fn sweep_expired(dir: &Path, now: SystemTime) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let modified = entry.metadata().and_then(|x| x.modified());
+        let Ok(age) = modified.map(|x| now.duration_since(x).unwrap_or_default()) else {
+            continue;
+        };
+        if age < REVEALED_TTL {
+            continue;
+        }
+        let lock = hand_lock(&path);
+        let _guard = lock.lock().unwrap();
+        // 写了一半残留的临时文件读不出来，也按未公开的处理
+        let revealed = read_json::<HandSeed>(&path).is_some_and(|x| x.revealed);
+        if revealed || age >= UNREVEALED_TTL {
+            if let Err(err) = fs::remove_file(&path) {
+                log_error_display("remove shuffle seed", &err);
+            }
+        }
+    }
+}
+
+// 后台定期清理过期的手牌
+pub fn start_sweeper() {
+    thread::spawn(|| loop {
+        sweep_expired(&data_dir("shuffle"), SystemTime::now());
+        thread::sleep(SWEEP_INTERVAL);
+    });
+}
+
+// 校验种子与承诺一致，且给出的手牌和公共牌由种子推出
+pub fn verify_shuffle(req: &VerifyShuffleReq) -> VerifyShuffleRsp {
+    let mut rsp = VerifyShuffleRsp {
+        code: 0,
+        valid: false,
+        mismatches: vec![],
+        hands: vec![],
+        board: Board::default(),
+        msg: "".to_string(),
+    };
+    if !players_valid(req.players) || req.hands.len() > req.players {
+        rsp.code = error_model::ERROR_INVALID;
+        rsp.msg = format!(
+            "players should be between 2 and {}, and hands should not be more than players",
+            MAX_PLAYERS
+        );
+        return rsp;
+    }
+    if sha256_hex(req.server_seed.as_bytes()) != req.commitment.to_lowercase() {
+        rsp.mismatches
+            .push("sha256(server_seed) does not match commitment".to_string());
+    }
+    let deck = shuffle_deck(&req.server_seed, &req.client_seed, req.nonce);
+    (rsp.hands, rsp.board) = deal(&deck, req.players);
+    for (seat, hands) in req.hands.iter().enumerate() {
+        if let Some(hands) = hands {
+            // 两张手牌不计顺序
+            let expected = rsp.hands[seat];
+            if hands != &expected && hands != &[expected[1], expected[0]] {
+                rsp.mismatches.push(format!(
+                    "seat {} should be {}{}",
+                    seat + 1,
+                    rsp.hands[seat][0],
+                    rsp.hands[seat][1]
+                ));
+            }
+        }
+    }
+    if req.board.cards() != &rsp.board.cards()[..req.board.len()] {
+        rsp.mismatches.push(format!(
+            "board should be {}",
+            rsp.board.prefix(req.board.len())
+        ));
+    }
+    rsp.valid = rsp.mismatches.is_empty();
+    return rsp;
+}
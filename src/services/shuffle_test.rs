@@ -0,0 +1,148 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::card::{parse_cards, CardSet};
+    use crate::models::model::{
+        ShuffleBoardReq, ShuffleDealReq, ShuffleRevealReq, Street, VerifyShuffleReq,
+    };
+    use crate::services::shuffle::{
+        deal, sha256_hex, shuffle_board, shuffle_commit, shuffle_deal, shuffle_deck,
+        shuffle_reveal, sweep_expired, verify_shuffle, REVEALED_TTL, UNREVEALED_TTL,
+    };
+    use crate::utils::store::data_dir;
+    use std::fs;
+    use std::time::SystemTime;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_shuffle_deck() {
+        let deck = shuffle_deck("server", "client", 7);
+        let set: CardSet = deck.iter().copied().collect();
+        assert_eq!(set.len(), 52);
+        // 协议不能变，否则历史牌局无法验证
+        let top: String = deck[..10].iter().map(|x| x.to_string()).collect();
+        assert_eq!(top, "6sAc8cJc5cQsTdQd7d8d");
+        let (hands, board) = deal(&deck, 3);
+        assert_eq!(hands[1], [deck[1], deck[4]]);
+        assert_eq!(board.to_string(), "Qd7d8d4s6c");
+        assert_ne!(deck, shuffle_deck("server", "client", 8));
+        assert_eq!(
+            sha256_hex(b"server"),
+            "b3eacd33433b31b5252351032c9b3e7a2e7aa7738d5decdf0dd6c62680853c06"
+        );
+    }
+
+    #[test]
+    fn test_commit_deal_reveal() {
+        let commit = shuffle_commit();
+        assert_eq!(commit.code, 0);
+        let new_deal = |client_seed: &str, seat: usize| ShuffleDealReq {
+            hand_id: commit.hand_id.clone(),
+            token: commit.token.clone(),
+            client_seed: client_seed.to_string(),
+            nonce: 1,
+            players: 6,
+            seat,
+        };
+        let dealt = shuffle_deal(new_deal("lucky", 2));
+        assert_eq!(dealt.code, 0);
+        let hands = dealt.hands.unwrap();
+        assert_eq!(shuffle_deal(new_deal("lucky", 2)).hands, Some(hands));
+        assert_ne!(shuffle_deal(new_deal("other", 1)).code, 0);
+        assert_ne!(shuffle_deal(new_deal("lucky", 7)).code, 0);
+        // 没有token不能发牌
+        let mut req = new_deal("lucky", 1);
+        req.token = "guess".to_string();
+        assert_eq!(shuffle_deal(req).msg, "token does not match");
+
+        let board_req = |street: Street| ShuffleBoardReq {
+            hand_id: commit.hand_id.clone(),
+            token: commit.token.clone(),
+            street,
+        };
+        let flop = shuffle_board(board_req(Street::Flop));
+        assert_eq!(flop.board.len(), 3);
+        let river = shuffle_board(board_req(Street::River)).board;
+        assert_eq!(river.prefix(3), flop.board);
+
+        let reveal_req = |token: &str| ShuffleRevealReq {
+            hand_id: commit.hand_id.clone(),
+            token: token.to_string(),
+        };
+        assert_ne!(shuffle_reveal(reveal_req("guess")).code, 0);
+        let reveal = shuffle_reveal(reveal_req(&commit.token));
+        assert_eq!(reveal.code, 0);
+        assert_eq!(reveal.commitment, commit.commitment);
+        assert_eq!(reveal.client_seed, "lucky");
+        // 公开后不能再发牌，重复公开结果相同
+        assert_eq!(
+            shuffle_deal(new_deal("lucky", 1)).msg,
+            "hand is already revealed"
+        );
+        assert_eq!(
+            shuffle_reveal(reveal_req(&commit.token)).server_seed,
+            reveal.server_seed
+        );
+
+        let mut req = VerifyShuffleReq {
+            server_seed: reveal.server_seed,
+            commitment: commit.commitment,
+            client_seed: reveal.client_seed,
+            nonce: reveal.nonce,
+            players: 6,
+            hands: vec![None, Some(hands)],
+            board: flop.board,
+        };
+        let rsp = verify_shuffle(&req);
+        assert!(rsp.valid);
+        assert_eq!(rsp.board, river);
+
+        let cards = parse_cards(&rsp.board.prefix(2).to_string()).unwrap();
+        req.hands[0] = Some([cards[0], cards[1]]);
+        req.commitment = sha256_hex(b"other");
+        let rsp = verify_shuffle(&req);
+        assert!(!rsp.valid);
+        assert_eq!(rsp.mismatches.len(), 2);
+    }
+
+    #[test]
+    fn test_unknown_hand() {
+        let req = ShuffleRevealReq {
+            hand_id: "../../etc/passwd".to_string(),
+            token: "".to_string(),
+        };
+        assert_eq!(shuffle_reveal(req).msg, "hand_id is invalid");
+        let req = ShuffleRevealReq {
+            hand_id: "6f1c1b8e-0000-4000-8000-000000000000".to_string(),
+            token: "".to_string(),
+        };
+        assert_eq!(shuffle_reveal(req).msg, "hand_id is not committed");
+    }
+
+    #[test]
+    fn test_sweep_expired() {
+        // 复制到单独的目录里清理，不影响其他测试的手牌
+        let dir = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let revealed = shuffle_commit();
+        let reveal_req = ShuffleRevealReq {
+            hand_id: revealed.hand_id.clone(),
+            token: revealed.token.clone(),
+        };
+        assert_eq!(shuffle_reveal(reveal_req).code, 0);
+        let pending = shuffle_commit();
+        for hand_id in [&revealed.hand_id, &pending.hand_id] {
+            let name = format!("{}.json", hand_id);
+            fs::copy(data_dir("shuffle").join(&name), dir.join(&name)).unwrap();
+        }
+        let exists = |hand_id: &str| dir.join(format!("{}.json", hand_id)).exists();
+
+        let now = SystemTime::now();
+        sweep_expired(&dir, now);
+        assert!(exists(&revealed.hand_id) && exists(&pending.hand_id));
+        sweep_expired(&dir, now + REVEALED_TTL);
+        assert!(!exists(&revealed.hand_id) && exists(&pending.hand_id));
+        sweep_expired(&dir, now + UNREVEALED_TTL);
+        assert!(!exists(&pending.hand_id));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,181 @@
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::models::card::{Board, Card, CardSet};
+use crate::models::error_model;
+use crate::models::model::{
+    HiLoGame, HiLoUserCards, StudGame, StudPlayer, StudRatingReq, StudRatingRsp,
+};
+use crate::services::evaluator::{deck_valid, draw_cards, for_each_ordered_runout, Evaluator};
+use crate::services::hi_lo::{best_high, split_pot, HiLoTally};
+use crate::utils::log::log_info_display;
+
+// 每人最终的牌数
+const STUD_CARDS: usize = 7;
+const MAX_UP_CARDS: usize = 4;
+const MAX_DOWN_CARDS: usize = 3;
+
+// 剩余3张及以上未发的牌时的采样次数
+const STUD_SAMPLES: u64 = 10000;
+
+// 7张牌中A-5低牌的牌力，越小越好：先比牌型(无对、一对、两对……)，再从大到小比点数，A最小
+pub fn razz_low(cards: &[Card]) -> u32 {
+    let mut best = u32::MAX;
+    let n = cards.len();
+    // 7张中任选5张，即去掉其中2张
+    for i in 0..n {
+        for j in i + 1..n {
+            let mut counts = [0u8; 13];
+            cards
+                .iter()
+                .enumerate()
+                .filter(|(k, _)| *k != i && *k != j)
+                .for_each(|(_, x)| counts[(x.rank().index() + 1) % 13] += 1);
+            best = best.min(low_value(&counts));
+        }
+    }
+    best
+}
+
+fn low_value(counts: &[u8; 13]) -> u32 {
+    // 按张数从多到少、点数从大到小排列
+    let mut groups: Vec<(u8, usize)> = (0..13)
+        .filter(|x| counts[*x] > 0)
+        .map(|x| (counts[x], x))
+        .collect();
+    groups.sort_unstable_by(|a, b| b.cmp(a));
+    let category = match groups.iter().map(|x| x.0).collect::<Vec<u8>>()[..] {
+        [1, ..] => 0,
+        [2, 1, ..] => 1,
+        [2, 2, ..] => 2,
+        [3, 1, ..] => 3,
+        [3, 2] => 4,
+        _ => 5,
+    };
+    groups
+        .iter()
+        .fold(category, |acc, x| (acc << 4) | x.1 as u32)
+        << (4 * (5 - groups.len()))
+}
+
+// 每个玩家分到的(高牌, 低牌)份额，以整个底池为1
+fn stud_shares(game: StudGame, hands: &[HiLoUserCards]) -> Vec<(f64, f64)> {
+    match game {
+        StudGame::Stud8 => split_pot(HiLoGame::HoldemHiLo, hands, &[]),
+        StudGame::Stud => {
+            let highs: Vec<u16> = hands
+                .iter()
+                .map(|x| best_high(HiLoGame::HoldemHiLo, &x.hands, &[]))
+                .collect();
+            let best = *highs.iter().max().unwrap();
+            let winners = highs.iter().filter(|x| **x == best).count() as f64;
+            highs
+                .iter()
+                .map(|x| (if *x == best { 1.0 / winners } else { 0.0 }, 0.0))
+                .collect()
+        }
+        StudGame::Razz => {
+            let lows: Vec<u32> = hands.iter().map(|x| razz_low(&x.hands)).collect();
+            let best = *lows.iter().min().unwrap();
+            let winners = lows.iter().filter(|x| **x == best).count() as f64;
+            lows.iter()
+                .map(|x| (0.0, if *x == best { 1.0 / winners } else { 0.0 }))
+                .collect()
+        }
+    }
+}
+
+fn stud_valid(clients: &[StudPlayer], dead_cards: &[Card]) -> Result<(), String> {
+    if clients.len() < 2 {
+        return Err("clients.len is lt 2".to_string());
+    }
+    if clients
+        .iter()
+        .any(|x| x.up_cards.len() > MAX_UP_CARDS || x.down_cards.len() > MAX_DOWN_CARDS)
+    {
+        return Err(format!(
+            "up cards should be at most {} and down cards at most {}",
+            MAX_UP_CARDS, MAX_DOWN_CARDS
+        ));
+    }
+    let mut known: CardSet = dead_cards.iter().copied().collect();
+    let duplicated = clients
+        .iter()
+        .flat_map(|x| x.up_cards.iter().chain(&x.down_cards))
+        .any(|x| !known.insert(*x));
+    if duplicated {
+        return Err("req has duplicates".to_string());
+    }
+    Ok(())
+}
+
+impl Evaluator {
+    pub fn stud_rating(&self, req: StudRatingReq) -> StudRatingRsp {
+        let mut rsp = StudRatingRsp {
+            code: 0,
+            clients_rate: vec![],
+            low_rate: 0,
+            samples: 0,
+            seed: 0,
+            msg: "".to_string(),
+        };
+        if let Err(msg) = stud_valid(&req.clients, &req.dead_cards) {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = msg;
+            return rsp;
+        }
+        let known_cards = req
+            .clients
+            .iter()
+            .flat_map(|x| x.up_cards.iter().chain(&x.down_cards).copied());
+        let mut deck = self.get_alive_cards(&Board::default(), &req.dead_cards, known_cards);
+        // 每张未发的牌属于哪个玩家
+        let mut hands = vec![];
+        let mut slots = vec![];
+        for (i, client) in req.clients.iter().enumerate() {
+            let cards: Vec<Card> = client
+                .up_cards
+                .iter()
+                .chain(&client.down_cards)
+                .copied()
+                .collect();
+            slots.extend(std::iter::repeat_n(i, STUD_CARDS - cards.len()));
+            hands.push(HiLoUserCards {
+                hands: cards,
+                uid: client.uid.clone(),
+            });
+        }
+        if let Err(msg) = deck_valid(deck.len(), slots.len()) {
+            rsp.code = error_model::ERROR_INVALID;
+            rsp.msg = msg;
+            return rsp;
+        }
+        let known_lens: Vec<usize> = hands.iter().map(|x| x.hands.len()).collect();
+        let mut tally = HiLoTally::new(hands.len());
+        let mut deal = |hands: &mut Vec<HiLoUserCards>, cards: &[Card]| {
+            for (hand, len) in hands.iter_mut().zip(&known_lens) {
+                hand.hands.truncate(*len);
+            }
+            for (slot, card) in slots.iter().zip(cards) {
+                hands[*slot].hands.push(*card);
+            }
+            tally.add(&stud_shares(req.game, hands));
+        };
+        if slots.len() <= 2 {
+            // 剩余不超过2张时穷举，按顺序发给不同的玩家
+            for_each_ordered_runout(&deck, slots.len(), &mut |cards| deal(&mut hands, cards));
+        } else {
+            let seed = req.seed.unwrap_or_else(|| thread_rng().gen());
+            log_info_display("stud rating seed", &seed);
+            rsp.seed = seed;
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            for _ in 0..STUD_SAMPLES {
+                deal(&mut hands, draw_cards(&mut rng, &mut deck, slots.len()));
+            }
+        }
+        rsp.clients_rate = tally.clients_rate(req.clients.iter().map(|x| &x.uid));
+        rsp.low_rate = tally.low_rate();
+        rsp.samples = tally.total;
+        return rsp;
+    }
+}
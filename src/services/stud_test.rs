@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::card::parse_cards;
+    use crate::models::error_model;
+    use crate::models::model::{StudGame, StudPlayer, StudRatingReq, StudRatingRsp};
+    use crate::services::evaluator::Evaluator;
+    use crate::services::stud::razz_low;
+
+    fn player(uid: &str, down: &str, up: &str) -> StudPlayer {
+        StudPlayer {
+            uid: uid.to_string(),
+            up_cards: parse_cards(up).unwrap(),
+            down_cards: parse_cards(down).unwrap(),
+        }
+    }
+
+    fn rating(game: StudGame, clients: Vec<StudPlayer>) -> StudRatingRsp {
+        let req = StudRatingReq {
+            game,
+            clients,
+            dead_cards: vec![],
+            seed: Some(3),
+        };
+        Evaluator {}.stud_rating(req)
+    }
+
+    fn river() -> Vec<StudPlayer> {
+        vec![
+            player("a", "As2s9c", "3h4h5hKd"),
+            player("b", "6c7c8c", "2d3d4dQd"),
+        ]
+    }
+
+    #[test]
+    fn test_razz_low() {
+        let wheel = razz_low(&parse_cards("As2s3h4h5hKdKc").unwrap());
+        let seven = razz_low(&parse_cards("6c7c4d3d2dQdKc").unwrap());
+        let pair = razz_low(&parse_cards("AsAd2c2h3s3dKc").unwrap());
+        assert!(wheel < seven);
+        assert!(seven < pair);
+        // 同花顺不影响低牌
+        assert_eq!(wheel, razz_low(&parse_cards("As2s3s4s5sKdKc").unwrap()));
+        // 一对时先比对子的大小
+        let twos = razz_low(&parse_cards("2s2dKcKhQsQdJc").unwrap());
+        let threes = razz_low(&parse_cards("3s3d4c4h5s5d6c").unwrap());
+        assert!(twos < threes);
+    }
+
+    #[test]
+    fn test_river_games() {
+        // a是A2345，高牌顺子、低牌最好
+        let rsp = rating(StudGame::Stud, river());
+        assert_eq!(rsp.code, 0);
+        assert_eq!(rsp.samples, 1);
+        assert_eq!(rsp.clients_rate[0].scoop, 10000);
+        assert_eq!(rsp.low_rate, 0);
+
+        let rsp = rating(StudGame::Stud8, river());
+        assert_eq!(rsp.clients_rate[0].scoop, 10000);
+        assert_eq!(rsp.low_rate, 10000);
+
+        let rsp = rating(StudGame::Razz, river());
+        assert_eq!(rsp.clients_rate[0].rate, 10000);
+        assert_eq!(rsp.clients_rate[1].rate, 0);
+    }
+
+    #[test]
+    fn test_remaining_deals() {
+        // b还差最后一张暗牌，穷举剩余的39张
+        let clients = vec![
+            player("a", "As2s9c", "3h4h5hKd"),
+            player("b", "6c7c", "2d3d4dQd"),
+        ];
+        let rsp = rating(StudGame::Razz, clients);
+        assert_eq!(rsp.samples, 39);
+        assert!(rsp.clients_rate[0].rate > 9000);
+
+        // 三街随机发剩余的牌，同一个种子结果相同
+        let third = || {
+            vec![
+                player("a", "AsAh", "Kd"),
+                player("b", "7c8c", "9c"),
+                player("c", "", "2d"),
+            ]
+        };
+        let first = rating(StudGame::Stud, third());
+        let second = rating(StudGame::Stud, third());
+        assert_eq!(first.samples, 10000);
+        assert_eq!(first.seed, 3);
+        let rates: Vec<u64> = first.clients_rate.iter().map(|x| x.rate).collect();
+        let again: Vec<u64> = second.clients_rate.iter().map(|x| x.rate).collect();
+        assert_eq!(rates, again);
+        assert!(rates[0] > rates[1] && rates[0] > rates[2]);
+    }
+
+    #[test]
+    fn test_stud_invalid() {
+        let rsp = rating(StudGame::Stud, vec![player("a", "As", "")]);
+        assert_eq!(rsp.code, error_model::ERROR_INVALID);
+        let rsp = rating(
+            StudGame::Stud,
+            vec![player("a", "As", "Kd"), player("b", "As", "")],
+        );
+        assert_eq!(rsp.code, error_model::ERROR_INVALID);
+        // 8人都发满7张需要56张牌
+        let clients = (0..8).map(|x| player(&x.to_string(), "", "")).collect();
+        assert_eq!(
+            rating(StudGame::Stud, clients).code,
+            error_model::ERROR_INVALID
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use uuid::Uuid;
+
+// 线上环境与日志目录一样放在/data下，多个实例需要挂载同一个目录才能共享数据
+pub fn data_dir(name: &str) -> PathBuf {
+    match env::var("PROFILE") {
+        Ok(_) => PathBuf::from("/data").join(name),
+        Err(_) => PathBuf::from(".").join(name),
+    }
+}
+
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let text = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+// 先写同目录下的临时文件再改名，读的一方不会看到写了一半的文件
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let text = serde_json::to_string(value)?;
+    let temp = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+    fs::write(&temp, text)?;
+    fs::rename(&temp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}